@@ -0,0 +1,182 @@
+use crate::cpu_test::{CpuTestMethod, CpuTestMethodStatus, TestMethodResponse};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The optional stage after the per-core tests that loads all tested cores at once
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AllCoreConfig {
+    pub enabled: bool,
+    /// The duration of the stage, split between the methods like the duration per core
+    pub duration: String,
+}
+
+impl Default for AllCoreConfig {
+    fn default() -> Self {
+        AllCoreConfig {
+            enabled: false,
+            duration: "10m".to_string(),
+        }
+    }
+}
+
+impl AllCoreConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        parse_duration::parse(&self.duration).map_err(|e| {
+            format!(
+                "Invalid all-core duration {}: {}. Examples: 1h, 30m, 10s",
+                self.duration, e
+            )
+        })?;
+
+        Ok(())
+    }
+}
+
+/// The result of the all-core stage, shown as its own entry next to the cores
+#[derive(Debug, Clone, Serialize)]
+pub struct AllCoreStatus {
+    /// Shown as the title, e.g. "All cores" or a set of cores of the load sweep
+    pub name: String,
+    pub core_ids: Vec<usize>,
+    pub method_response: HashMap<CpuTestMethod, TestMethodResponse>,
+    /// The cores a test program reported an error for
+    pub failed_core_ids: Vec<usize>,
+}
+
+impl AllCoreStatus {
+    /// Returns if all cores passed all methods together or any failed
+    /// None if the stage did not run completely
+    pub fn passed(&self) -> Option<bool> {
+        let any_failed = self
+            .method_response
+            .values()
+            .any(|method_response| method_response.state == CpuTestMethodStatus::Failed);
+        if any_failed || !self.failed_core_ids.is_empty() {
+            return Some(false);
+        }
+
+        let all_passed = !self.method_response.is_empty()
+            && self
+                .method_response
+                .values()
+                .all(|method_response| method_response.state == CpuTestMethodStatus::Success);
+        all_passed.then_some(true)
+    }
+
+    /// Returns the response of the method, which has to be part of the stage
+    pub fn method_response_mut(&mut self, method: &CpuTestMethod) -> &mut TestMethodResponse {
+        self.method_response.get_mut(method).unwrap()
+    }
+
+    /// Attributes a failure of the method to the core
+    pub fn add_failed_core(&mut self, core_id: usize) {
+        if !self.failed_core_ids.contains(&core_id) {
+            self.failed_core_ids.push(core_id);
+            self.failed_core_ids.sort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status_with(states: &[(CpuTestMethod, CpuTestMethodStatus)]) -> AllCoreStatus {
+        AllCoreStatus {
+            name: "All cores".to_string(),
+            core_ids: vec![0, 1, 2],
+            method_response: states
+                .iter()
+                .map(|(method, state)| {
+                    (
+                        *method,
+                        TestMethodResponse {
+                            method: *method,
+                            state: *state,
+                            current_secs: 0,
+                            total_secs: 60,
+                            error: None,
+                            prime95_progress: None,
+                            wake_latency: None,
+                        },
+                    )
+                })
+                .collect(),
+            failed_core_ids: vec![],
+        }
+    }
+
+    #[test]
+    fn passed_all_methods() {
+        // GIVEN
+        let status = status_with(&[
+            (CpuTestMethod::Prime95, CpuTestMethodStatus::Success),
+            (CpuTestMethod::StressKernel, CpuTestMethodStatus::Success),
+        ]);
+
+        // WHEN
+        let result = status.passed();
+
+        // THEN
+        assert_eq!(result, Some(true));
+    }
+
+    #[test]
+    fn passed_with_failed_core() {
+        // GIVEN
+        let mut status = status_with(&[
+            (CpuTestMethod::Prime95, CpuTestMethodStatus::Failed),
+            (CpuTestMethod::StressKernel, CpuTestMethodStatus::Idle),
+        ]);
+
+        // WHEN
+        status.add_failed_core(2);
+        status.add_failed_core(0);
+        status.add_failed_core(2);
+        let result = status.passed();
+
+        // THEN
+        assert_eq!(result, Some(false));
+        assert_eq!(status.failed_core_ids, vec![0, 2]);
+    }
+
+    #[test]
+    fn passed_incomplete() {
+        // GIVEN
+        let status = status_with(&[
+            (CpuTestMethod::Prime95, CpuTestMethodStatus::Success),
+            (CpuTestMethod::StressKernel, CpuTestMethodStatus::Skipped),
+        ]);
+
+        // WHEN
+        let result = status.passed();
+
+        // THEN
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn validate_duration_only_if_enabled() {
+        // GIVEN
+        let disabled = AllCoreConfig {
+            enabled: false,
+            duration: "ten minutes".to_string(),
+        };
+        let enabled = AllCoreConfig {
+            enabled: true,
+            ..disabled.clone()
+        };
+
+        // WHEN
+        let results = (disabled.validate(), enabled.validate());
+
+        // THEN
+        assert!(results.0.is_ok());
+        assert!(results.1.unwrap_err().contains("all-core duration"));
+    }
+}
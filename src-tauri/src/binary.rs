@@ -0,0 +1,439 @@
+use crate::cpu_test::CpuTestMethod;
+use crate::process;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+const VERSION_TIMEOUT: Duration = Duration::from_secs(5);
+const VERSION_DIR_HASH_LENGTH: usize = 12;
+
+lazy_static! {
+    /// Private per-user directory the embedded binaries are extracted to
+    /// Uses $XDG_RUNTIME_DIR and falls back to the user cache directory
+    pub static ref RUNTIME_DIR: PathBuf = dirs::runtime_dir()
+        .or_else(dirs::cache_dir)
+        .unwrap()
+        .join("pbo-assistant");
+    /// The resolved binaries by backend and configured external path, as the version probes take a while
+    static ref RESOLVED_BINARIES: Mutex<HashMap<(CpuTestMethod, Option<String>), BinaryInfo>> =
+        Mutex::new(HashMap::new());
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+pub enum BinarySource {
+    Embedded,
+    External,
+}
+
+/// Describes which stress test binary a backend will use
+#[derive(Debug, Clone, Serialize)]
+pub struct BinaryInfo {
+    pub method: CpuTestMethod,
+    pub source: BinarySource,
+    pub path: String,
+    pub version: Option<String>,
+    /// Set if the configured external binary could not be used
+    pub warning: Option<String>,
+}
+
+/// Resolves the binary to use for the given backend
+/// Uses the external binary if configured and usable, otherwise falls back to the embedded one
+pub fn resolve(
+    method: CpuTestMethod,
+    external_path: &Option<String>,
+    embedded_path: &Path,
+    version_args: &[&str],
+) -> BinaryInfo {
+    let mut warning = None;
+
+    if let Some(external_path) = external_path.as_ref().filter(|path| !path.is_empty()) {
+        match check_executable(Path::new(external_path)) {
+            Ok(()) => {
+                let version = detect_version(external_path, version_args);
+
+                if version.is_some() {
+                    return BinaryInfo {
+                        method,
+                        source: BinarySource::External,
+                        path: external_path.clone(),
+                        version,
+                        warning: None,
+                    };
+                }
+
+                warning = Some(format!(
+                    "Could not detect the version of {}, using the embedded binary",
+                    external_path
+                ));
+            }
+            Err(error) => {
+                warning = Some(format!("{}, using the embedded binary", error));
+            }
+        }
+    }
+
+    let embedded_path = embedded_path.display().to_string();
+    let version = if Path::new(&embedded_path).exists() {
+        detect_version(&embedded_path, version_args)
+    } else {
+        None
+    };
+
+    BinaryInfo {
+        method,
+        source: BinarySource::Embedded,
+        path: embedded_path,
+        version,
+        warning,
+    }
+}
+
+/// Returns the binary resolved before for the backend and external path, resolves it otherwise
+/// Concurrent callers wait for each other, so the embedded binaries are extracted only once
+/// A cached binary that fails the check is resolved again, e.g. if the extracted files were removed
+pub fn cached(
+    method: CpuTestMethod,
+    external_path: &Option<String>,
+    check: impl FnOnce(&BinaryInfo) -> Result<(), String>,
+    resolve: impl FnOnce() -> BinaryInfo,
+) -> BinaryInfo {
+    let mut resolved_binaries = RESOLVED_BINARIES.lock().unwrap();
+    let key = (method, external_path.clone());
+
+    if let Some(binary_info) = resolved_binaries.get(&key) {
+        match check(binary_info) {
+            Ok(()) => return binary_info.clone(),
+            Err(error) => println!("{}, resolving the binary again", error),
+        }
+    }
+
+    let binary_info = resolve();
+    resolved_binaries.insert(key, binary_info.clone());
+    binary_info
+}
+
+/// Returns the cache directory for the embedded files with the given hash
+/// A new embedded version gets a new directory, so extracted files can be reused across runs
+pub fn version_dir(name: &str, sha256: &str) -> PathBuf {
+    RUNTIME_DIR.join(format!("{}-{}", name, &sha256[..VERSION_DIR_HASH_LENGTH]))
+}
+
+/// Whether the name follows `version_dir`, e.g. "mprime-0123456789ab"
+fn is_version_dir_name(name: &str) -> bool {
+    name.rsplit_once('-').is_some_and(|(prefix, hash)| {
+        !prefix.is_empty()
+            && hash.len() == VERSION_DIR_HASH_LENGTH
+            && hash.chars().all(|c| c.is_ascii_hexdigit())
+    })
+}
+
+/// Removes the version directories in the base directory except the given ones
+/// Other directories were not created by us and are kept
+/// Returns the names of the removed directories
+pub fn remove_stale_dirs(base_dir: &Path, current_dirs: &[PathBuf]) -> Result<Vec<String>, String> {
+    let mut removed = vec![];
+
+    if !base_dir.exists() {
+        return Ok(removed);
+    }
+
+    let entries = fs::read_dir(base_dir)
+        .map_err(|e| format!("Failed to read {}: {}", base_dir.display(), e))?;
+
+    for entry in entries {
+        let path = entry
+            .map_err(|e| format!("Failed to read {}: {}", base_dir.display(), e))?
+            .path();
+
+        let is_version_dir = path
+            .file_name()
+            .is_some_and(|name| is_version_dir_name(&name.to_string_lossy()));
+        if !path.is_dir() || !is_version_dir || current_dirs.contains(&path) {
+            continue;
+        }
+
+        fs::remove_dir_all(&path)
+            .map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+        removed.push(path.file_name().unwrap().to_string_lossy().to_string());
+    }
+
+    removed.sort();
+    Ok(removed)
+}
+
+/// Creates the given directory inside the runtime directory, only accessible by the current user
+pub fn create_private_dir(path: &Path) {
+    fs::create_dir_all(path).expect("Failed to create directory");
+
+    // Including the directories created in between
+    for dir in path
+        .ancestors()
+        .take_while(|dir| dir.starts_with(RUNTIME_DIR.as_path()))
+    {
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))
+            .expect("Failed to change permissions");
+    }
+}
+
+/// Restricts the directory and all directories below it to the current user, e.g. after unpacking
+pub fn make_dirs_private(path: &Path) -> std::io::Result<()> {
+    fs::set_permissions(path, fs::Permissions::from_mode(0o700))?;
+
+    for entry in fs::read_dir(path)? {
+        let path = entry?.path();
+        if path.is_dir() && !path.is_symlink() {
+            make_dirs_private(&path)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the file and makes sure the written content matches
+pub fn write_verified(path: &Path, content: &[u8], mode: u32) -> Result<(), String> {
+    fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+        .map_err(|e| format!("Failed to change permissions of {}: {}", path.display(), e))?;
+
+    verify_file(path, &sha256(content))
+}
+
+/// Checks if the SHA-256 hash of the file matches the expected hash
+pub fn verify_file(path: &Path, expected_sha256: &str) -> Result<(), String> {
+    let content =
+        fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let actual_sha256 = sha256(&content);
+    if actual_sha256 != expected_sha256 {
+        return Err(format!(
+            "Integrity check failed for {}: expected SHA-256 {}, got {}",
+            path.display(),
+            expected_sha256,
+            actual_sha256
+        ));
+    }
+
+    Ok(())
+}
+
+/// Returns the hex encoded SHA-256 hash of the given content
+pub fn sha256(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+/// Checks if the given path is an executable file
+fn check_executable(path: &Path) -> Result<(), String> {
+    let metadata = std::fs::metadata(path)
+        .map_err(|e| format!("Binary {} is not available: {}", path.display(), e))?;
+
+    if !metadata.is_file() {
+        return Err(format!("Binary {} is not a file", path.display()));
+    }
+
+    if metadata.permissions().mode() & 0o111 == 0 {
+        return Err(format!("Binary {} is not executable", path.display()));
+    }
+
+    Ok(())
+}
+
+/// Runs the binary with the given arguments and extracts the version from its output
+fn detect_version(path: &str, version_args: &[&str]) -> Option<String> {
+    let output = process::run_with_timeout(path, version_args, VERSION_TIMEOUT)?;
+    parse_version(&output)
+}
+
+/// Finds the first version number in the given output, e.g. "30.19" or "0.8.5"
+fn parse_version(output: &str) -> Option<String> {
+    output
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .map(|word| word.trim_start_matches('v'))
+        .find(|word| {
+            word.contains('.')
+                && word
+                    .split('.')
+                    .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+        })
+        .map(|word| word.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_version_mprime() {
+        // GIVEN
+        let output = "Mprime version 30.19, RdtscTiming=1";
+
+        // WHEN
+        let result = parse_version(output);
+
+        // THEN
+        assert_eq!(result, Some("30.19".to_string()));
+    }
+
+    #[test]
+    fn parse_version_ycruncher() {
+        // GIVEN
+        let output = "\ny-cruncher v0.8.5 Build 9543\n\nCopyright 2008-2024 Alexander J. Yee\n";
+
+        // WHEN
+        let result = parse_version(output);
+
+        // THEN
+        assert_eq!(result, Some("0.8.5".to_string()));
+    }
+
+    #[test]
+    fn parse_version_missing() {
+        // GIVEN
+        let output = "Usage: mprime [-c] [-d] [-t]";
+
+        // WHEN
+        let result = parse_version(output);
+
+        // THEN
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn sha256_known_value() {
+        // GIVEN
+        let content = b"abc";
+
+        // WHEN
+        let result = sha256(content);
+
+        // THEN
+        assert_eq!(
+            result,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn verify_file_detects_modification() {
+        // GIVEN
+        let path = std::env::temp_dir().join("pbo-assistant-verify-file-test");
+        write_verified(&path, b"original", 0o600).unwrap();
+        fs::write(&path, b"modified").unwrap();
+
+        // WHEN
+        let result = verify_file(&path, &sha256(b"original"));
+
+        // THEN
+        fs::remove_file(&path).unwrap();
+        assert!(result.unwrap_err().contains("Integrity check failed"));
+    }
+
+    #[test]
+    fn remove_stale_dirs_keeps_current() {
+        // GIVEN
+        let base_dir = std::env::temp_dir().join("pbo-assistant-remove-stale-dirs-test");
+        let current_dir = base_dir.join("mprime-0123456789ab");
+        fs::create_dir_all(&current_dir).unwrap();
+        fs::create_dir_all(base_dir.join("mprime-ba9876543210")).unwrap();
+        fs::create_dir_all(base_dir.join("ycruncher-fedcba987654")).unwrap();
+        fs::create_dir_all(base_dir.join("ycruncher")).unwrap();
+        fs::create_dir_all(base_dir.join("notes-2024")).unwrap();
+
+        // WHEN
+        let result = remove_stale_dirs(&base_dir, std::slice::from_ref(&current_dir));
+
+        // THEN
+        let current_dir_exists = current_dir.exists();
+        let foreign_dirs_exist =
+            base_dir.join("ycruncher").exists() && base_dir.join("notes-2024").exists();
+        fs::remove_dir_all(&base_dir).unwrap();
+        assert_eq!(
+            result.unwrap(),
+            vec![
+                "mprime-ba9876543210".to_string(),
+                "ycruncher-fedcba987654".to_string()
+            ]
+        );
+        assert!(current_dir_exists);
+        assert!(foreign_dirs_exist);
+    }
+
+    #[test]
+    fn make_dirs_private_nested() {
+        // GIVEN
+        let base_dir = std::env::temp_dir().join("pbo-assistant-make-dirs-private-test");
+        let nested_dir = base_dir.join("ycruncher").join("Binaries");
+        fs::create_dir_all(&nested_dir).unwrap();
+        fs::set_permissions(&nested_dir, fs::Permissions::from_mode(0o755)).unwrap();
+
+        // WHEN
+        let result = make_dirs_private(&base_dir);
+
+        // THEN
+        let mode = fs::metadata(&nested_dir).unwrap().permissions().mode() & 0o777;
+        fs::remove_dir_all(&base_dir).unwrap();
+        assert!(result.is_ok());
+        assert_eq!(mode, 0o700);
+    }
+
+    #[test]
+    fn cached_resolves_again_if_check_fails() {
+        // GIVEN
+        let external_path = Some("/pbo-assistant-cached-test".to_string());
+        let binary_info = |path: &str| BinaryInfo {
+            method: CpuTestMethod::IdleWake,
+            source: BinarySource::Embedded,
+            path: path.to_string(),
+            version: None,
+            warning: None,
+        };
+        cached(
+            CpuTestMethod::IdleWake,
+            &external_path,
+            |_| Ok(()),
+            || binary_info("first"),
+        );
+
+        // WHEN
+        let kept = cached(
+            CpuTestMethod::IdleWake,
+            &external_path,
+            |_| Ok(()),
+            || binary_info("second"),
+        );
+        let resolved_again = cached(
+            CpuTestMethod::IdleWake,
+            &external_path,
+            |_| Err("Removed".to_string()),
+            || binary_info("third"),
+        );
+
+        // THEN
+        assert_eq!(kept.path, "first");
+        assert_eq!(resolved_again.path, "third");
+    }
+
+    #[test]
+    fn resolve_missing_external_binary() {
+        // GIVEN
+        let external_path = Some("/nonexistent/mprime".to_string());
+
+        // WHEN
+        let result = resolve(
+            CpuTestMethod::Prime95,
+            &external_path,
+            Path::new("/nonexistent/embedded/mprime"),
+            &["-v"],
+        );
+
+        // THEN
+        assert_eq!(result.source, BinarySource::Embedded);
+        assert_eq!(result.path, "/nonexistent/embedded/mprime");
+        assert!(result.warning.unwrap().contains("not available"));
+    }
+}
@@ -0,0 +1,97 @@
+use crate::builtin::{self, BuiltinFailure, MethodControl, XorShift};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Load bursts are short, so the core boosts up and down often
+const MIN_BURST: Duration = Duration::from_millis(5);
+const MAX_BURST: Duration = Duration::from_millis(250);
+/// Short idle periods keep the core in shallow sleep states, long ones let it drop deeper
+const MIN_IDLE: Duration = Duration::from_millis(1);
+const MAX_IDLE: Duration = Duration::from_millis(500);
+/// Rounds of one verified computation, takes well below a millisecond
+const ROUNDS: u32 = 2_000;
+/// Result of `compute(ROUNDS)` on a stable CPU
+const GOLDEN_CHECKSUM: u64 = 0xD537_9B66_0071_4B4B;
+
+/// Alternates the core between load bursts and idle periods of random length until stopped
+/// Every computation of a burst is compared with the golden checksum, the first one right after the idle period
+pub fn run(physical_core_id: usize, control: &dyn MethodControl) -> Result<(), BuiltinFailure> {
+    let mut random = XorShift::new(physical_core_id as u64 + 1);
+    let mut transitions: u64 = 0;
+
+    while !control.should_stop() {
+        builtin::wait_while_paused(control);
+
+        let burst_end = Instant::now() + random.next_duration(MIN_BURST, MAX_BURST);
+        loop {
+            let checksum = compute(black_box(ROUNDS));
+            if checksum != GOLDEN_CHECKSUM {
+                return Err(BuiltinFailure::Verification(format!(
+                    "Checksum {:#x} instead of {:#x} after {} load transitions",
+                    checksum, GOLDEN_CHECKSUM, transitions
+                )));
+            }
+
+            if Instant::now() >= burst_end {
+                break;
+            }
+        }
+        transitions += 1;
+
+        builtin::sleep(random.next_duration(MIN_IDLE, MAX_IDLE), control);
+    }
+
+    println!(
+        "Boost transition test finished after {} load transitions",
+        transitions
+    );
+    Ok(())
+}
+
+/// Mixes integer and floating point operations, so both units are verified
+fn compute(rounds: u32) -> u64 {
+    let mut random = XorShift::new(0x9E37_79B9_7F4A_7C15);
+    let mut integer: u64 = 0;
+    let mut float: f64 = 1.0;
+
+    for round in 0..rounds {
+        let value = random.next_u64();
+        integer = integer
+            .wrapping_mul(0x2545_F491_4F6C_DD1D)
+            .wrapping_add(value)
+            .rotate_left(round % 64);
+        float = (float * 1.000_001 + (value >> 44) as f64).sqrt();
+    }
+
+    integer ^ float.to_bits()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtin::tests::CountingControl;
+    use std::cell::Cell;
+
+    #[test]
+    fn compute_matches_golden_checksum() {
+        // WHEN
+        let result = compute(black_box(ROUNDS));
+
+        // THEN
+        assert_eq!(result, GOLDEN_CHECKSUM);
+    }
+
+    #[test]
+    fn run_until_stopped() {
+        // GIVEN
+        let control = CountingControl {
+            checks_left: Cell::new(3),
+        };
+
+        // WHEN
+        let result = run(0, &control);
+
+        // THEN
+        assert_eq!(result, Ok(()));
+    }
+}
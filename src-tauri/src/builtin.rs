@@ -0,0 +1,122 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often a paused or idling built-in method checks if it should continue
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Lets a built-in method know when to stop or pause
+/// The methods run on a thread of the app, so they can not be signalled like the test programs
+pub trait MethodControl {
+    fn should_stop(&self) -> bool;
+    fn is_paused(&self) -> bool;
+}
+
+/// Why a built-in method ended before the time was up
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuiltinFailure {
+    /// A computation returned a wrong result, the core is unstable
+    Verification(String),
+    /// The method could not run, e.g. the thread could not be pinned
+    Error(String),
+}
+
+/// Blocks while the run is paused
+pub fn wait_while_paused(control: &dyn MethodControl) {
+    while control.is_paused() && !control.should_stop() {
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Sleeps for the given duration, but returns early if the method should stop
+pub fn sleep(duration: Duration, control: &dyn MethodControl) {
+    let end = Instant::now() + duration;
+
+    loop {
+        let now = Instant::now();
+        if now >= end || control.should_stop() {
+            return;
+        }
+        thread::sleep(POLL_INTERVAL.min(end - now));
+    }
+}
+
+/// Deterministic pseudo random numbers, so a run can be reproduced
+#[derive(Debug, Clone)]
+pub struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    pub fn new(seed: u64) -> XorShift {
+        // A state of zero would only produce zeros
+        XorShift { state: seed.max(1) }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// Returns a duration between min and max
+    pub fn next_duration(&mut self, min: Duration, max: Duration) -> Duration {
+        let range = (max - min).as_micros() as u64 + 1;
+        min + Duration::from_micros(self.next_u64() % range)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    /// Stops the method after the given number of checks
+    pub struct CountingControl {
+        pub checks_left: Cell<u32>,
+    }
+
+    impl MethodControl for CountingControl {
+        fn should_stop(&self) -> bool {
+            let checks_left = self.checks_left.get();
+            self.checks_left.set(checks_left.saturating_sub(1));
+            checks_left == 0
+        }
+
+        fn is_paused(&self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn next_duration_within_range() {
+        // GIVEN
+        let mut random = XorShift::new(0);
+        let min = Duration::from_millis(5);
+        let max = Duration::from_millis(20);
+
+        // WHEN
+        let durations: Vec<Duration> = (0..1000).map(|_| random.next_duration(min, max)).collect();
+
+        // THEN
+        assert!(durations
+            .iter()
+            .all(|duration| *duration >= min && *duration <= max));
+        assert!(durations.iter().any(|duration| *duration != durations[0]));
+    }
+
+    #[test]
+    fn sleep_returns_when_stopped() {
+        // GIVEN
+        let control = CountingControl {
+            checks_left: Cell::new(0),
+        };
+        let start = Instant::now();
+
+        // WHEN
+        sleep(Duration::from_secs(10), &control);
+
+        // THEN
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
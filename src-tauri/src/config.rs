@@ -1,17 +1,150 @@
+use crate::all_core::AllCoreConfig;
 use crate::cpu_test::CpuTestMethod;
+use crate::load_sweep::LoadSweepConfig;
+use crate::mprime::Prime95Config;
+use crate::offset_model::{self, OffsetModel};
+use crate::profile::OffsetProfile;
+use crate::thermal::ThermalConfig;
+use crate::ycruncher::YCruncherConfig;
+use chrono::Utc;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
-use std::path::PathBuf;
-use std::sync::{Arc, RwLock};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
 use strum::IntoEnumIterator;
 
+/// Changes within this time are written to disk together
+const WRITE_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// How often the config file is checked for changes made outside the app
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Current version of the config schema, increased on incompatible changes
+pub const CONFIG_VERSION: u32 = 2;
+
+/// Missing fields fall back to their defaults, so older configs keep loading
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AppConfig {
+    pub version: u32,
     pub test_duration_per_core: String,
     pub cores_to_test: String,
     pub active_test_methods: Vec<CpuTestMethod>,
     pub offset_per_core: HashMap<usize, i32>,
+    /// None detects the model from the CPU vendor
+    pub offset_model: Option<OffsetModel>,
+    pub apply_offsets_via_smu: bool,
+    pub prime95: Prime95Config,
+    pub ycruncher: YCruncherConfig,
+    pub thermal: ThermalConfig,
+    pub all_core: AllCoreConfig,
+    pub load_sweep: LoadSweepConfig,
+    pub profiles: Vec<OffsetProfile>,
+    /// Name of the profile the offsets per core belong to
+    pub active_profile: Option<String>,
+}
+
+/// The config together with the reason if it had to be reset
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadedConfig {
+    pub config: AppConfig,
+    pub warning: Option<String>,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        AppConfig {
+            version: CONFIG_VERSION,
+            test_duration_per_core: "10m".to_string(),
+            cores_to_test: "".to_string(),
+            active_test_methods: CpuTestMethod::iter().collect(),
+            offset_per_core: HashMap::new(),
+            offset_model: None,
+            apply_offsets_via_smu: false,
+            prime95: Prime95Config::default(),
+            ycruncher: YCruncherConfig::default(),
+            thermal: ThermalConfig::default(),
+            all_core: AllCoreConfig::default(),
+            load_sweep: LoadSweepConfig::default(),
+            profiles: vec![],
+            active_profile: None,
+        }
+    }
+}
+
+impl AppConfig {
+    /// The configured offset model or the one detected from the CPU vendor
+    pub fn resolved_offset_model(&self) -> OffsetModel {
+        offset_model::resolve(self.offset_model)
+    }
+
+    /// Changes the offset model, None detects it from the CPU vendor
+    /// The offsets are cleared if the model changes, as they are in the unit of the previous one
+    pub fn set_offset_model(&mut self, offset_model: Option<OffsetModel>) {
+        let previous_model = self.resolved_offset_model();
+        self.offset_model = offset_model;
+
+        if self.resolved_offset_model() != previous_model {
+            self.offset_per_core.clear();
+            self.active_profile = None;
+        }
+    }
+
+    /// Offsets can only be applied at runtime for the Curve Optimizer
+    pub fn applies_offsets_at_runtime(&self) -> bool {
+        self.apply_offsets_via_smu && self.resolved_offset_model().info().runtime_apply
+    }
+
+    /// Checks the values the user can enter, e.g. before starting a test
+    pub fn validate(&self, physical_core_count: usize) -> Result<(), String> {
+        parse_duration::parse(&self.test_duration_per_core).map_err(|e| {
+            format!(
+                "Invalid duration per core {}: {}. Examples: 1h, 30m, 10s",
+                self.test_duration_per_core, e
+            )
+        })?;
+
+        for core in self
+            .cores_to_test
+            .split(',')
+            .filter(|core| !core.trim().is_empty())
+        {
+            let core_id = core
+                .trim()
+                .parse::<usize>()
+                .map_err(|e| format!("Invalid core to test {}: {}", core.trim(), e))?;
+            if core_id >= physical_core_count {
+                return Err(format!(
+                    "Core {} does not exist, the CPU has {} physical cores",
+                    core_id, physical_core_count
+                ));
+            }
+        }
+
+        if self.active_test_methods.is_empty() {
+            return Err("At least one test method must be selected".to_string());
+        }
+
+        self.resolved_offset_model()
+            .validate_all(&self.offset_per_core)?;
+
+        if self.ycruncher.memory_mib == Some(0) {
+            return Err("y-cruncher memory must be greater than 0 MiB".to_string());
+        }
+
+        self.thermal.validate()?;
+        self.all_core.validate()?;
+        self.load_sweep.validate()?;
+
+        Ok(())
+    }
 }
 
 lazy_static! {
@@ -21,43 +154,684 @@ lazy_static! {
         .join("config.json");
 }
 
-/// Load configuration from file
-pub fn load_config(config_wirte_lock: &Arc<RwLock<bool>>) -> AppConfig {
-    if !CONFIG_PATH.exists() {
-        let new_config = AppConfig {
-            test_duration_per_core: "10m".to_string(),
-            cores_to_test: "".to_string(),
-            active_test_methods: CpuTestMethod::iter().collect(),
-            offset_per_core: HashMap::new(),
+/// The single source of the config, shared by all commands
+/// Changes are applied in memory under a lock, then written to disk debounced and atomically
+#[derive(Debug, Clone)]
+pub struct ConfigStore {
+    inner: Arc<ConfigStoreInner>,
+}
+
+#[derive(Debug)]
+struct ConfigStoreInner {
+    path: PathBuf,
+    debounce: Duration,
+    state: Mutex<ConfigStoreState>,
+    /// Serializes the writes, so an older config never overwrites a newer one
+    write_lock: Mutex<()>,
+}
+
+#[derive(Debug, Default)]
+struct ConfigStoreState {
+    config: Option<AppConfig>,
+    /// The config has changes that are not written yet
+    write_pending: bool,
+    /// A debounced write is waiting to run
+    write_scheduled: bool,
+    /// Counts the changes, so a write only marks the config it wrote as written
+    revision: u64,
+    /// Why the config was reset on load, taken once by take_load_warning
+    load_warning: Option<String>,
+    /// The file content as last read or written by the store, to detect changes by others
+    synced_content: Option<String>,
+    /// Modification time and size of the synced file, the content is only compared if they differ
+    synced_stamp: Option<(SystemTime, u64)>,
+    /// The unreadable config could not be backed up, it is not overwritten until fixed by hand
+    in_memory_only: bool,
+}
+
+impl ConfigStore {
+    pub fn new(path: PathBuf) -> ConfigStore {
+        ConfigStore::with_debounce(path, WRITE_DEBOUNCE)
+    }
+
+    pub fn with_debounce(path: PathBuf, debounce: Duration) -> ConfigStore {
+        ConfigStore {
+            inner: Arc::new(ConfigStoreInner {
+                path,
+                debounce,
+                state: Mutex::new(ConfigStoreState::default()),
+                write_lock: Mutex::new(()),
+            }),
+        }
+    }
+
+    /// Returns the current config, read from file on first use
+    /// A config that can not be read is backed up and replaced by the default config,
+    /// the reason can be retrieved once with take_load_warning
+    /// If the backup fails, the default config is only kept in memory
+    pub fn load(&self) -> AppConfig {
+        let mut state = self.inner.state.lock().unwrap();
+        self.loaded(&mut state).clone()
+    }
+
+    /// Applies the change to the config and schedules writing it
+    /// The config stays unchanged if the change fails
+    pub fn update<T, E>(
+        &self,
+        change: impl FnOnce(&mut AppConfig) -> Result<T, E>,
+    ) -> Result<T, E> {
+        let mut state = self.inner.state.lock().unwrap();
+        let mut config = self.loaded(&mut state).clone();
+
+        let result = change(&mut config)?;
+
+        state.config = Some(config);
+        self.schedule_write(&mut state);
+        Ok(result)
+    }
+
+    /// Replaces the whole config
+    pub fn save(&self, config: AppConfig) {
+        let mut state = self.inner.state.lock().unwrap();
+        state.config = Some(config);
+        self.schedule_write(&mut state);
+    }
+
+    /// Writes the current config immediately, e.g. before the app exits
+    pub fn flush(&self) -> Result<(), String> {
+        let _write_guard = self.inner.write_lock.lock().unwrap();
+
+        let (config, revision) = {
+            let state = self.inner.state.lock().unwrap();
+            if !state.write_pending || state.in_memory_only {
+                return Ok(());
+            }
+            (state.config.clone(), state.revision)
         };
 
-        save_config(&new_config, config_wirte_lock);
+        let Some(config) = config else {
+            return Ok(());
+        };
+
+        let config_str = serde_json::to_string(&config).unwrap();
+        // The config stays pending if the write fails, so the next flush retries it
+        write_atomic(&self.inner.path, config_str.as_bytes())?;
+        let mut state = self.inner.state.lock().unwrap();
+        if state.revision == revision {
+            state.write_pending = false;
+        }
+        state.synced_content = Some(config_str);
+        state.synced_stamp = file_stamp(&self.inner.path);
+
+        Ok(())
+    }
+
+    /// Reloads the config if the file was changed outside the app, e.g. edited by hand
+    /// Returns the reloaded config, None if unchanged, or why the changed config was rejected
+    /// The changed config wins over changes that are not written yet
+    pub fn reload_if_changed(
+        &self,
+        physical_core_count: usize,
+    ) -> Result<Option<AppConfig>, String> {
+        let _write_guard = self.inner.write_lock.lock().unwrap();
+        let mut state = self.inner.state.lock().unwrap();
+
+        // Only read the file if it was touched since it was last synced
+        let stamp = file_stamp(&self.inner.path);
+        if stamp.is_none() || stamp == state.synced_stamp {
+            return Ok(None);
+        }
+        state.synced_stamp = stamp;
+
+        let Ok(config_str) = fs::read_to_string(&self.inner.path) else {
+            return Ok(None);
+        };
+        if state.synced_content.as_ref() == Some(&config_str) {
+            return Ok(None);
+        }
+
+        // Remember the content, so a rejected change is only reported once
+        state.synced_content = Some(config_str.clone());
+
+        let config = parse_config(&config_str)?;
+        config.validate(physical_core_count)?;
 
-        return new_config;
+        state.config = Some(config.clone());
+        state.write_pending = false;
+        state.in_memory_only = false;
+        Ok(Some(config))
     }
 
-    let config_str = std::fs::read_to_string(CONFIG_PATH.as_path()).unwrap();
-    let config: AppConfig = serde_json::from_str(&config_str).unwrap();
-    config
+    /// Returns why the config was reset on the last load, only once
+    pub fn take_load_warning(&self) -> Option<String> {
+        self.inner.state.lock().unwrap().load_warning.take()
+    }
+
+    fn loaded<'a>(&self, state: &'a mut ConfigStoreState) -> &'a AppConfig {
+        if state.config.is_none() {
+            let config = self.read_or_reset(state);
+            state.config = Some(config);
+            state.synced_content = fs::read_to_string(&self.inner.path).ok();
+            state.synced_stamp = file_stamp(&self.inner.path);
+        }
+
+        state.config.as_ref().unwrap()
+    }
+
+    /// Reads the config file, returns the default config if it is missing or can not be read
+    /// The default config is only written if the unreadable one was backed up
+    fn read_or_reset(&self, state: &mut ConfigStoreState) -> AppConfig {
+        let path = &self.inner.path;
+        if !path.exists() {
+            self.schedule_write(state);
+            return AppConfig::default();
+        }
+
+        match read_config(path) {
+            Ok(config) => config,
+            Err(error) => {
+                let warning = match backup_config(path) {
+                    Ok(backup_path) => {
+                        self.schedule_write(state);
+                        format!(
+                            "{}. The config was reset, the previous one is kept as {}",
+                            error,
+                            backup_path.display()
+                        )
+                    }
+                    Err(backup_error) => {
+                        state.in_memory_only = true;
+                        format!(
+                            "{}. The defaults are used without saving them, {}",
+                            error, backup_error
+                        )
+                    }
+                };
+                println!("{}", warning);
+                state.load_warning = Some(warning);
+
+                AppConfig::default()
+            }
+        }
+    }
+
+    /// Writes the config after the debounce time, changes in between are written together
+    fn schedule_write(&self, state: &mut ConfigStoreState) {
+        state.write_pending = true;
+        state.revision += 1;
+        if state.write_scheduled {
+            return;
+        }
+        state.write_scheduled = true;
+
+        let store = self.clone();
+        thread::spawn(move || {
+            thread::sleep(store.inner.debounce);
+            store.inner.state.lock().unwrap().write_scheduled = false;
+            if let Err(error) = store.flush() {
+                println!("Failed to save the config: {}", error);
+            }
+        });
+    }
+}
+
+/// Checks the config file for changes made outside the app in the background
+/// Reports the reloaded config or why the changed config was rejected
+pub fn watch(
+    store: ConfigStore,
+    physical_core_count: usize,
+    on_change: impl Fn(Result<AppConfig, String>) + Send + 'static,
+) {
+    thread::spawn(move || loop {
+        thread::sleep(WATCH_INTERVAL);
+
+        match store.reload_if_changed(physical_core_count) {
+            Ok(Some(config)) => on_change(Ok(config)),
+            Ok(None) => {}
+            Err(error) => on_change(Err(error)),
+        }
+    });
+}
+
+/// Returns the modification time and size of the file, None if it does not exist
+fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
 }
 
-/// Save configuration to file
-pub fn save_config(config: &AppConfig, config_write_lock: &Arc<RwLock<bool>>) {
-    let is_someone_already_writing = *config_write_lock.read().unwrap();
+/// Writes the file atomically: into a temporary file that is synced to disk, then renamed
+pub fn write_atomic(path: &Path, content: &[u8]) -> Result<(), String> {
+    let dir = path.parent().unwrap();
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    let tmp_path = path.with_extension("tmp");
+    let mut file = File::create(&tmp_path)
+        .map_err(|e| format!("Failed to create {}: {}", tmp_path.display(), e))?;
+    file.write_all(content)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("Failed to write {}: {}", tmp_path.display(), e))?;
 
-    if is_someone_already_writing {
-        return;
-    } else {
-        *config_write_lock.write().unwrap() = true;
-    };
+    fs::rename(&tmp_path, path)
+        .map_err(|e| format!("Failed to replace {}: {}", path.display(), e))?;
 
-    // Check if config file exists, if not create folder structure
-    if !CONFIG_PATH.exists() {
-        std::fs::create_dir_all(CONFIG_PATH.parent().unwrap()).unwrap();
+    // Sync the directory, so the rename itself is persisted
+    File::open(dir)
+        .and_then(|dir| dir.sync_all())
+        .map_err(|e| format!("Failed to sync {}: {}", dir.display(), e))
+}
+
+/// Reads the config and migrates it to the current version
+fn read_config(path: &Path) -> Result<AppConfig, String> {
+    let config_str = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    parse_config(&config_str)
+}
+
+/// Parses the config of any known version
+pub fn parse_config(config_str: &str) -> Result<AppConfig, String> {
+    let mut value: Value =
+        serde_json::from_str(config_str).map_err(|e| format!("Invalid config: {}", e))?;
+    migrate(&mut value)?;
+    serde_json::from_value(value).map_err(|e| format!("Invalid config: {}", e))
+}
+
+/// Migrates the config step by step from its version to the current version
+/// Configs without version are from before the schema was versioned, i.e. version 1
+fn migrate(value: &mut Value) -> Result<(), String> {
+    let config = value
+        .as_object_mut()
+        .ok_or("Invalid config: not an object".to_string())?;
+
+    let version = config.get("version").and_then(Value::as_u64).unwrap_or(1) as u32;
+
+    if version > CONFIG_VERSION {
+        return Err(format!(
+            "Config version {} is newer than the supported version {}",
+            version, CONFIG_VERSION
+        ));
+    }
+
+    if version < 2 {
+        // Version 1 could contain test methods that no longer exist
+        if let Some(Value::Array(methods)) = config.get_mut("active_test_methods") {
+            methods.retain(|method| {
+                method
+                    .as_str()
+                    .is_some_and(|method| CpuTestMethod::from_str(method).is_ok())
+            });
+        }
+    }
+
+    config.insert("version".to_string(), Value::from(CONFIG_VERSION));
+    Ok(())
+}
+
+/// Copies the config next to it with a timestamp, e.g. config.json.20240101-120000.bak
+fn backup_config(path: &Path) -> Result<PathBuf, String> {
+    let backup_path =
+        path.with_extension(format!("json.{}.bak", Utc::now().format("%Y%m%d-%H%M%S")));
+    fs::copy(path, &backup_path)
+        .map_err(|e| format!("failed to back up {}: {}", path.display(), e))?;
+
+    Ok(backup_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile;
+
+    #[test]
+    fn parse_config_version_1() {
+        // GIVEN
+        let config_str = r#"{
+            "test_duration_per_core": "30m",
+            "cores_to_test": "0,1",
+            "active_test_methods": ["Prime95", "Linpack"],
+            "offset_per_core": {"0": -20, "1": -15}
+        }"#;
+
+        // WHEN
+        let result = parse_config(config_str);
+
+        // THEN
+        let config = result.unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.test_duration_per_core, "30m");
+        assert_eq!(config.active_test_methods, vec![CpuTestMethod::Prime95]);
+        assert_eq!(config.offset_per_core[&0], -20);
+        assert!(!config.apply_offsets_via_smu);
+        assert!(config.profiles.is_empty());
+    }
+
+    #[test]
+    fn parse_config_missing_fields() {
+        // GIVEN
+        let config_str = r#"{"version": 2, "cores_to_test": "3"}"#;
+
+        // WHEN
+        let result = parse_config(config_str);
+
+        // THEN
+        let config = result.unwrap();
+        assert_eq!(config.cores_to_test, "3");
+        assert_eq!(config.test_duration_per_core, "10m");
+    }
+
+    #[test]
+    fn parse_config_newer_version() {
+        // GIVEN
+        let config_str = r#"{"version": 99}"#;
+
+        // WHEN
+        let result = parse_config(config_str);
+
+        // THEN
+        assert!(result.unwrap_err().contains("newer"));
+    }
+
+    #[test]
+    fn parse_config_corrupt() {
+        // GIVEN
+        let config_str = r#"{"test_duration_per_core": "10m", "#;
+
+        // WHEN
+        let result = parse_config(config_str);
+
+        // THEN
+        assert!(result.unwrap_err().starts_with("Invalid config"));
+    }
+
+    #[test]
+    fn backup_config_keeps_content() {
+        // GIVEN
+        let dir = std::env::temp_dir().join("pbo-assistant-backup-config-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.json");
+        std::fs::write(&path, "corrupt").unwrap();
+
+        // WHEN
+        let result = backup_config(&path);
+
+        // THEN
+        let backup_content = std::fs::read_to_string(result.unwrap()).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(backup_content, "corrupt");
+    }
+
+    #[test]
+    fn validate_invalid_values() {
+        // GIVEN
+        let configs = [
+            AppConfig {
+                test_duration_per_core: "ten minutes".to_string(),
+                ..Default::default()
+            },
+            AppConfig {
+                cores_to_test: "0, 12".to_string(),
+                ..Default::default()
+            },
+            AppConfig {
+                offset_model: Some(OffsetModel::CurveOptimizer),
+                offset_per_core: HashMap::from([(0, -35)]),
+                ..Default::default()
+            },
+            AppConfig {
+                active_test_methods: vec![],
+                all_core: AllCoreConfig {
+                    enabled: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            AppConfig {
+                active_test_methods: vec![],
+                load_sweep: LoadSweepConfig {
+                    enabled: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ];
+
+        // WHEN
+        let results: Vec<Result<(), String>> =
+            configs.iter().map(|config| config.validate(12)).collect();
+
+        // THEN
+        assert!(results[0].as_ref().unwrap_err().contains("duration"));
+        assert!(results[1].as_ref().unwrap_err().contains("Core 12"));
+        assert!(results[2].as_ref().unwrap_err().contains("Core 0"));
+        assert!(results[3].as_ref().unwrap_err().contains("test method"));
+        assert!(results[4].as_ref().unwrap_err().contains("test method"));
+    }
+
+    #[test]
+    fn validate_default_config() {
+        // GIVEN
+        let config = AppConfig::default();
+
+        // WHEN
+        let result = config.validate(8);
+
+        // THEN
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn set_offset_model_clears_offsets_of_previous_model() {
+        // GIVEN
+        let config = AppConfig {
+            offset_model: Some(OffsetModel::VoltageOffset),
+            offset_per_core: HashMap::from([(0, -100)]),
+            active_profile: Some("Undervolt".to_string()),
+            ..Default::default()
+        };
+
+        // WHEN
+        let mut same_model = config.clone();
+        same_model.set_offset_model(Some(OffsetModel::VoltageOffset));
+        let mut other_model = config.clone();
+        other_model.set_offset_model(Some(OffsetModel::CurveOptimizer));
+
+        // THEN
+        assert_eq!(same_model.offset_per_core, config.offset_per_core);
+        assert!(other_model.offset_per_core.is_empty());
+        assert_eq!(other_model.active_profile, None);
+        assert!(other_model.validate(8).is_ok());
+    }
+
+    fn test_store(name: &str) -> (ConfigStore, PathBuf) {
+        let dir = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("config.json");
+        (
+            ConfigStore::with_debounce(path.clone(), Duration::from_millis(20)),
+            path,
+        )
+    }
+
+    #[test]
+    fn concurrent_set_offset_keeps_all_offsets() {
+        // GIVEN
+        let (store, path) = test_store("pbo-assistant-concurrent-set-offset-test");
+
+        // WHEN
+        let threads: Vec<_> = (0..32)
+            .map(|core_id| {
+                let store = store.clone();
+                thread::spawn(move || {
+                    store.update(|config| {
+                        profile::set_offset(config, core_id, -(core_id as i32 % 30));
+                        Ok::<(), String>(())
+                    })
+                })
+            })
+            .collect();
+        threads
+            .into_iter()
+            .for_each(|thread| thread.join().unwrap().unwrap());
+        store.flush().unwrap();
+
+        // THEN
+        let written = read_config(&path).unwrap();
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert_eq!(written.offset_per_core.len(), 32);
+        assert_eq!(written.offset_per_core[&31], -1);
+    }
+
+    #[test]
+    fn update_is_written_debounced() {
+        // GIVEN
+        let (store, path) = test_store("pbo-assistant-debounced-write-test");
+
+        // WHEN
+        for offset in 0..10 {
+            store
+                .update(|config| {
+                    profile::set_offset(config, 0, -offset);
+                    Ok::<(), String>(())
+                })
+                .unwrap();
+        }
+        thread::sleep(Duration::from_millis(200));
+
+        // THEN
+        let written = read_config(&path).unwrap();
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert_eq!(written.offset_per_core[&0], -9);
+    }
+
+    #[test]
+    fn failed_write_is_retried() {
+        // GIVEN
+        let (store, path) = test_store("pbo-assistant-failed-write-test");
+        let dir = path.parent().unwrap();
+        fs::write(dir, "").unwrap();
+        store
+            .update(|config| {
+                profile::set_offset(config, 0, -5);
+                Ok::<(), String>(())
+            })
+            .unwrap();
+        let failed = store.flush();
+        thread::sleep(Duration::from_millis(100));
+        fs::remove_file(dir).unwrap();
+
+        // WHEN
+        let result = store.flush();
+
+        // THEN
+        let written = read_config(&path);
+        fs::remove_dir_all(dir).unwrap();
+        assert!(failed.is_err());
+        assert_eq!(result, Ok(()));
+        assert_eq!(written.unwrap().offset_per_core[&0], -5);
     }
 
-    let config_str = serde_json::to_string(config).unwrap();
-    std::fs::write(CONFIG_PATH.as_path(), config_str).unwrap();
+    #[test]
+    fn failed_update_keeps_config() {
+        // GIVEN
+        let (store, _) = test_store("pbo-assistant-failed-update-test");
 
-    *config_write_lock.write().unwrap() = false;
+        // WHEN
+        let result: Result<(), String> = store.update(|config| {
+            config.cores_to_test = "1".to_string();
+            Err("Invalid".to_string())
+        });
+
+        // THEN
+        assert!(result.is_err());
+        assert_eq!(store.load().cores_to_test, "");
+    }
+
+    #[test]
+    fn failed_backup_keeps_config_file() {
+        // GIVEN
+        let (store, path) = test_store("pbo-assistant-failed-backup-test");
+        // A directory can neither be read nor copied as config
+        fs::create_dir_all(&path).unwrap();
+
+        // WHEN
+        let config = store.load();
+        store
+            .update(|config| {
+                config.cores_to_test = "1".to_string();
+                Ok::<(), String>(())
+            })
+            .unwrap();
+        let result = store.flush();
+
+        // THEN
+        assert_eq!(config.cores_to_test, AppConfig::default().cores_to_test);
+        assert!(result.is_ok());
+        assert_eq!(store.load().cores_to_test, "1");
+        assert!(path.is_dir());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn write_atomic_replaces_file() {
+        // GIVEN
+        let dir = std::env::temp_dir().join("pbo-assistant-write-atomic-test");
+        let path = dir.join("config.json");
+        write_atomic(&path, b"old").unwrap();
+
+        // WHEN
+        let result = write_atomic(&path, b"new");
+
+        // THEN
+        let content = fs::read_to_string(&path).unwrap();
+        let tmp_exists = path.with_extension("tmp").exists();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(result.is_ok());
+        assert_eq!(content, "new");
+        assert!(!tmp_exists);
+    }
+
+    #[test]
+    fn reload_if_changed_external_edit() {
+        // GIVEN
+        let (store, path) = test_store("pbo-assistant-reload-external-edit-test");
+        store.save(AppConfig::default());
+        store.flush().unwrap();
+        let unchanged = store.reload_if_changed(8);
+        let edited = AppConfig {
+            cores_to_test: "2,3".to_string(),
+            ..Default::default()
+        };
+        fs::write(&path, serde_json::to_string(&edited).unwrap()).unwrap();
+
+        // WHEN
+        let result = store.reload_if_changed(8);
+
+        // THEN
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert_eq!(unchanged.unwrap().map(|config| config.cores_to_test), None);
+        assert_eq!(result.unwrap().unwrap().cores_to_test, "2,3");
+        assert_eq!(store.load().cores_to_test, "2,3");
+    }
+
+    #[test]
+    fn reload_if_changed_rejects_invalid_edit() {
+        // GIVEN
+        let (store, path) = test_store("pbo-assistant-reload-invalid-edit-test");
+        store.save(AppConfig::default());
+        store.flush().unwrap();
+        let edited = AppConfig {
+            cores_to_test: "42".to_string(),
+            ..Default::default()
+        };
+        fs::write(&path, serde_json::to_string(&edited).unwrap()).unwrap();
+
+        // WHEN
+        let result = store.reload_if_changed(8);
+        let second_result = store.reload_if_changed(8);
+
+        // THEN
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert!(result.unwrap_err().contains("Core 42"));
+        assert!(second_result.unwrap().is_none());
+        assert_eq!(store.load().cores_to_test, "");
+    }
 }
@@ -1,18 +1,33 @@
 use itertools::Itertools;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
+use std::path::Path;
+
+const SYSFS_CPU_PATH: &str = "/sys/devices/system/cpu";
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct CpusInfo {
     pub cpus: Vec<CpuInfo>,
     pub physical_cores: usize,
     pub logical_cores: usize,
+    pub vendor: CpuVendor,
+    pub family: u32,
+    pub model: u32,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum CpuVendor {
+    Amd,
+    Intel,
+    Unknown,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct CpuInfo {
     pub id: usize,
     pub proc_cpu_id: usize,
+    pub proc_core_id: usize,
     pub thread_count: usize,
     pub name: String,
     pub mhz: f64,
@@ -41,6 +56,18 @@ pub fn get_first_logical_core_id_for(physical_core_id: usize) -> usize {
     logical_cpu_id
 }
 
+/// Returns the "core id" of /proc/cpuinfo for the physical core id
+pub fn get_proc_core_id_for(physical_core_id: usize) -> usize {
+    let cpu_info = get().unwrap();
+
+    cpu_info
+        .cpus
+        .iter()
+        .find(|cpu| cpu.id == physical_core_id)
+        .map(|cpu| cpu.proc_core_id)
+        .unwrap_or(physical_core_id)
+}
+
 pub fn get_cpu_freq(physical_core_id: usize) -> f64 {
     let cpu_info = get().unwrap();
 
@@ -62,11 +89,16 @@ pub fn get() -> Result<CpusInfo, String> {
     let cpus: Vec<CpuInfo> = parse_cpus_info(&proc_cpuinfo_string);
 
     let (physical_cores, logical_cores) = get_cores_count(&proc_cpuinfo_string);
+    let (family, model) = get_family_and_model(&proc_cpuinfo_string);
+    let vendor = get_vendor(&proc_cpuinfo_string);
 
     Ok(CpusInfo {
         cpus,
         physical_cores,
         logical_cores,
+        vendor,
+        family,
+        model,
     })
 }
 
@@ -102,6 +134,7 @@ fn transform_to_cpu_info(parsed_cpu_info: Vec<ProcCpuInfo>) -> Vec<CpuInfo> {
         physical_cores.push(CpuInfo {
             id: iter_index,
             proc_cpu_id: first_thread.processor,
+            proc_core_id: first_thread.core_id,
             thread_count: threads_per_core.len(),
             name: first_thread.name.clone(),
             mhz: first_thread.mhz,
@@ -177,6 +210,26 @@ fn get_cores_count(proc_cpuinfo_string: &str) -> (usize, usize) {
     (physical_cores, logical_cores)
 }
 
+fn get_family_and_model(proc_cpuinfo_string: &str) -> (u32, u32) {
+    let family = get_first_proc_cpuinfo_property(proc_cpuinfo_string, "cpu family")
+        .parse()
+        .unwrap_or(0);
+
+    let model = get_first_proc_cpuinfo_property(proc_cpuinfo_string, "model\t")
+        .parse()
+        .unwrap_or(0);
+
+    (family, model)
+}
+
+fn get_vendor(proc_cpuinfo_string: &str) -> CpuVendor {
+    match get_first_proc_cpuinfo_property(proc_cpuinfo_string, "vendor_id").as_str() {
+        "AuthenticAMD" => CpuVendor::Amd,
+        "GenuineIntel" => CpuVendor::Intel,
+        _ => CpuVendor::Unknown,
+    }
+}
+
 fn get_first_proc_cpuinfo_property(proc_cpu_info: &str, property: &str) -> String {
     proc_cpu_info
         .lines()
@@ -198,6 +251,42 @@ pub fn get_logical_cores() -> usize {
     get().unwrap().logical_cores
 }
 
+/// Returns the physical core ids per L3 group, the cores sharing one L3 cache
+/// This is a CCD, but on Zen 2 the L3 cache belongs to a CCX, so each CCX is its own group
+pub fn get_l3_groups() -> Vec<Vec<usize>> {
+    let cpu_info = get().unwrap();
+    group_by_l3_cache(&cpu_info.cpus, Path::new(SYSFS_CPU_PATH))
+}
+
+/// Groups the physical cores by the id of their L3 cache
+/// All cores are in one group if the cache topology is not available
+fn group_by_l3_cache(cpus: &[CpuInfo], sysfs_cpu_dir: &Path) -> Vec<Vec<usize>> {
+    let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+
+    for cpu in cpus {
+        let cpu_dir = sysfs_cpu_dir.join(format!("cpu{}", cpu.proc_cpu_id));
+        let Some(l3_cache_id) = get_l3_cache_id(&cpu_dir) else {
+            return vec![cpus.iter().map(|cpu| cpu.id).collect()];
+        };
+        groups.entry(l3_cache_id).or_default().push(cpu.id);
+    }
+
+    groups.into_values().collect()
+}
+
+/// Reads the id of the level 3 cache from the cache directories of a logical core
+fn get_l3_cache_id(cpu_dir: &Path) -> Option<usize> {
+    fs::read_dir(cpu_dir.join("cache"))
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|cache_dir| {
+            fs::read_to_string(cache_dir.join("level")).is_ok_and(|level| level.trim() == "3")
+        })
+        .and_then(|cache_dir| fs::read_to_string(cache_dir.join("id")).ok())
+        .and_then(|id| id.trim().parse().ok())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -231,6 +320,56 @@ mod tests {
         assert_eq!(result, "24");
     }
 
+    #[test]
+    fn test_get_family_and_model_amd() {
+        // GIVEN
+        let cpuinfo = AMD_HYPERTHREADING;
+
+        // WHEN
+        let result = get_family_and_model(cpuinfo);
+
+        // THEN
+        assert_eq!(result, (25, 33));
+    }
+
+    #[test]
+    fn test_get_vendor_amd() {
+        // GIVEN
+        let cpuinfo = AMD_HYPERTHREADING;
+
+        // WHEN
+        let result = get_vendor(cpuinfo);
+
+        // THEN
+        assert_eq!(result, CpuVendor::Amd);
+    }
+
+    #[test]
+    fn test_get_vendor_intel() {
+        // GIVEN
+        let cpuinfo = INTEL_HYPERTHREADING;
+
+        // WHEN
+        let result = get_vendor(cpuinfo);
+
+        // THEN
+        assert_eq!(result, CpuVendor::Intel);
+    }
+
+    #[test]
+    fn test_parse_cpus_info_amd_proc_core_id() {
+        // GIVEN
+        let cpuinfo = AMD_HYPERTHREADING;
+
+        // WHEN
+        let result = parse_cpus_info(cpuinfo);
+
+        // THEN
+        assert_eq!(result.len(), 12);
+        assert_eq!(result[5].proc_core_id, 5);
+        assert_eq!(result[6].proc_core_id, 8);
+    }
+
     #[test]
     fn group_by_core_id_single_core() {
         // GIVEN
@@ -331,4 +470,56 @@ mod tests {
         assert_eq!(result.len(), 1);
         assert_eq!(result.get(&0).unwrap().len(), 3);
     }
+
+    /// Creates a sysfs cpu directory with an L1 and an L3 cache per logical core
+    fn create_fake_sysfs_cpu(name: &str, l3_cache_ids: &[usize]) -> std::path::PathBuf {
+        let root = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&root);
+
+        for (processor, l3_cache_id) in l3_cache_ids.iter().enumerate() {
+            let cache_dir = root.join(format!("cpu{}/cache", processor));
+            for (index, level, id) in [(0, 1, processor), (3, 3, *l3_cache_id)] {
+                let index_dir = cache_dir.join(format!("index{}", index));
+                fs::create_dir_all(&index_dir).unwrap();
+                fs::write(index_dir.join("level"), format!("{}\n", level)).unwrap();
+                fs::write(index_dir.join("id"), format!("{}\n", id)).unwrap();
+            }
+        }
+        root
+    }
+
+    fn cpus(count: usize) -> Vec<CpuInfo> {
+        (0..count)
+            .map(|id| CpuInfo {
+                id,
+                proc_cpu_id: id,
+                proc_core_id: id,
+                thread_count: 1,
+                name: "AMD Ryzen 9".to_string(),
+                mhz: 3000.0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn group_by_l3_cache_two_ccds() {
+        // GIVEN
+        let root = create_fake_sysfs_cpu("pbo-assistant-sysfs-ccd-test", &[0, 0, 1, 1, 0]);
+
+        // WHEN
+        let result = group_by_l3_cache(&cpus(5), &root);
+
+        // THEN
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(result, vec![vec![0, 1, 4], vec![2, 3]]);
+    }
+
+    #[test]
+    fn group_by_l3_cache_without_topology() {
+        // WHEN
+        let result = group_by_l3_cache(&cpus(3), Path::new("/nonexistent/pbo-assistant-cpu"));
+
+        // THEN
+        assert_eq!(result, vec![vec![0, 1, 2]]);
+    }
 }
@@ -1,24 +1,190 @@
 use std::cmp::PartialEq;
 use std::collections::{HashMap, HashSet};
+use std::fmt::{Debug, Formatter};
 use std::hash::Hash;
 use std::io::BufRead;
 use std::ops::{Div, Mul};
+use std::path::Path;
 use std::process::Child;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, PoisonError, RwLock};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use strum_macros::{Display, EnumIter, EnumString};
 
-use crate::{cpu_info, mprime, process, ycruncher};
+use crate::all_core::{AllCoreConfig, AllCoreStatus};
+use crate::binary::BinaryInfo;
+use crate::builtin::{BuiltinFailure, MethodControl};
+use crate::config::ConfigStore;
+use crate::idle_wake::WakeLatency;
+use crate::load_sweep::{LoadSweepConfig, LoadSweepStatus, SetOutcome};
+use crate::mprime::{Prime95Config, Prime95Progress};
+use crate::offset_model::OffsetModel;
+use crate::thermal::{ThermalConfig, ThermalGuard};
+use crate::ycruncher::YCruncherConfig;
+use crate::{
+    boost_transition, cpu_info, idle_wake, ledger, load_sweep, mprime, process, stress_kernel,
+    thermal, ycruncher,
+};
 
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub test_status: Arc<RwLock<HashMap<usize, CpuTestStatus>>>,
     pub terminated_by_user: Arc<RwLock<bool>>,
-    pub config_write_lock: Arc<RwLock<bool>>,
+    pub config_store: ConfigStore,
+    pub events: TestEventSink,
+    pub run_state: Arc<RwLock<RunState>>,
+    /// What the user requested to skip, handled by the running method
+    pub skip_request: Arc<RwLock<Option<SkipTarget>>>,
+    /// None if the all-core stage is not part of the run
+    pub all_core_status: Arc<RwLock<Option<AllCoreStatus>>>,
+    /// The offsets the last run tested the cores with, the base of the offset suggestions
+    /// None during a run and once the suggestions were applied
+    pub tested_offsets: Arc<RwLock<Option<HashMap<usize, i32>>>>,
+}
+
+/// What to skip of a running test
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum SkipTarget {
+    /// Only the current method, the core continues with the next method
+    Method,
+    /// All remaining methods of the current core
+    Core,
+}
+
+/// Lifecycle of a test run, only one run can be active at a time
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+pub enum RunState {
+    Idle,
+    /// The config is validated and the offsets are applied
+    Preparing,
+    Running,
+    /// The test programs are suspended and the timer is frozen
+    Paused,
+    /// The user stopped the run, the current method is being aborted
+    Stopping,
+    Finished,
+}
+
+impl RunState {
+    /// Whether a run is in progress, so no other run can be started
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self,
+            RunState::Preparing | RunState::Running | RunState::Paused | RunState::Stopping
+        )
+    }
+
+    fn can_move_to(&self, next: RunState) -> bool {
+        matches!(
+            (self, next),
+            (RunState::Idle | RunState::Finished, RunState::Preparing)
+                | (
+                    RunState::Preparing,
+                    RunState::Running | RunState::Idle | RunState::Stopping
+                )
+                | (
+                    RunState::Running,
+                    RunState::Paused | RunState::Stopping | RunState::Finished
+                )
+                | (RunState::Paused, RunState::Running | RunState::Stopping)
+                | (RunState::Stopping, RunState::Finished)
+        )
+    }
+}
+
+impl AppState {
+    pub fn run_state(&self) -> RunState {
+        *self.run_state.read().unwrap()
+    }
+
+    /// Moves the run to the next state
+    /// Returns the current state if the transition is not allowed, e.g. starting a second run
+    pub fn move_run_state(&self, next: RunState) -> Result<(), RunState> {
+        self.move_run_state_with(next, || {})
+    }
+
+    /// Moves the run to the next state and runs the action while still holding the state,
+    /// so concurrent transitions can not interleave with it
+    fn move_run_state_with(&self, next: RunState, action: impl FnOnce()) -> Result<(), RunState> {
+        let mut run_state = self.run_state.write().unwrap();
+        if !run_state.can_move_to(next) {
+            return Err(*run_state);
+        }
+
+        *run_state = next;
+        action();
+        Ok(())
+    }
+}
+
+/// A status change of a test run, pushed to the UI as it happens
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum TestEvent {
+    CoreStarted(CpuTestStatus),
+    MethodProgress {
+        core_id: usize,
+        method_response: TestMethodResponse,
+    },
+    ClockUpdate {
+        core_id: usize,
+        max_clock: u64,
+    },
+    Failure {
+        core_id: usize,
+        method: CpuTestMethod,
+    },
+    /// The core is None while cooling down between the methods of the all-core stage
+    Cooldown {
+        core_id: Option<usize>,
+        temperature: f64,
+        target: u32,
+    },
+    AllCoreProgress(AllCoreStatus),
+    RunFinished {
+        stopped_by_user: bool,
+        test_status: Vec<CpuTestStatus>,
+        all_core_status: Option<AllCoreStatus>,
+        load_sweep_status: Option<LoadSweepStatus>,
+    },
+}
+
+impl TestEvent {
+    /// The name of the event the UI listens to
+    pub fn name(&self) -> &'static str {
+        match self {
+            TestEvent::CoreStarted(_) => "core-started",
+            TestEvent::MethodProgress { .. } => "method-progress",
+            TestEvent::ClockUpdate { .. } => "clock-update",
+            TestEvent::Failure { .. } => "test-failure",
+            TestEvent::Cooldown { .. } => "cooldown",
+            TestEvent::AllCoreProgress(_) => "all-core-progress",
+            TestEvent::RunFinished { .. } => "run-finished",
+        }
+    }
+}
+
+/// Receives the test events, e.g. to emit them to the UI
+#[derive(Clone)]
+pub struct TestEventSink(Arc<dyn Fn(TestEvent) + Send + Sync>);
+
+impl TestEventSink {
+    pub fn new(on_event: impl Fn(TestEvent) + Send + Sync + 'static) -> TestEventSink {
+        TestEventSink(Arc::new(on_event))
+    }
+
+    pub fn emit(&self, event: TestEvent) {
+        (self.0)(event)
+    }
+}
+
+impl Debug for TestEventSink {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("TestEventSink")
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -35,6 +201,9 @@ pub struct TestMethodResponse {
     pub state: CpuTestMethodStatus,
     pub current_secs: u64,
     pub total_secs: u64,
+    pub error: Option<String>,
+    pub prime95_progress: Option<Prime95Progress>,
+    pub wake_latency: Option<WakeLatency>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -42,6 +211,46 @@ pub struct CpuTestConfig {
     pub duration_per_core: String,
     pub cores_to_test: Vec<usize>,
     pub test_methods: Vec<CpuTestMethod>,
+    pub prime95_config: Prime95Config,
+    pub ycruncher_config: YCruncherConfig,
+    /// The offsets the cores are tested with, recorded in the ledger
+    pub offset_per_core: HashMap<usize, i32>,
+    /// The model the offsets are interpreted with
+    pub offset_model: OffsetModel,
+    pub thermal_config: ThermalConfig,
+    pub all_core_config: AllCoreConfig,
+    pub load_sweep_config: LoadSweepConfig,
+}
+
+impl CpuTestStatus {
+    /// Returns if the core passed all methods or failed any
+    /// None if the core was not tested completely, e.g. stopped by the user or errored
+    pub fn passed(&self) -> Option<bool> {
+        let mut states = self
+            .method_response
+            .values()
+            .map(|method_response| method_response.state);
+
+        if self.verification_failed || states.any(|state| state == CpuTestMethodStatus::Failed) {
+            return Some(false);
+        }
+
+        let all_passed = !self.method_response.is_empty()
+            && self
+                .method_response
+                .values()
+                .all(|method_response| method_response.state == CpuTestMethodStatus::Success);
+        all_passed.then_some(true)
+    }
+}
+
+/// The resolved test program binaries and their settings for a test run
+#[derive(Debug, Clone)]
+struct TestPrograms {
+    prime95_binary: BinaryInfo,
+    ycruncher_binary: BinaryInfo,
+    ycruncher_config: YCruncherConfig,
+    thermal_guard: ThermalGuard,
 }
 
 #[derive(
@@ -50,6 +259,12 @@ pub struct CpuTestConfig {
 pub enum CpuTestMethod {
     Prime95,
     YCruncher,
+    /// Built-in, alternates between load bursts and idle periods
+    BoostTransition,
+    /// Built-in, integer, floating point and SIMD workloads checked against golden checksums
+    StressKernel,
+    /// Built-in, wakes the core from idle over and over
+    IdleWake,
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, EnumIter, Display, Serialize)]
@@ -58,11 +273,22 @@ pub enum CpuTestMethodStatus {
     Testing,
     Success,
     Failed,
+    Error,
+    /// Skipped by the user, neither passed nor failed
+    Skipped,
+    /// Aborted above the max temperature, neither passed nor failed
+    ThermalAbort,
 }
 
 pub fn run(app_state: AppState, config: &CpuTestConfig) {
-    mprime::initialize();
-    ycruncher::initialize();
+    let _finish_on_panic = FinishOnPanic(&app_state);
+
+    let test_programs = TestPrograms {
+        prime95_binary: mprime::initialize(&config.prime95_config),
+        ycruncher_binary: ycruncher::initialize(&config.ycruncher_config),
+        ycruncher_config: config.ycruncher_config.clone(),
+        thermal_guard: ThermalGuard::new(&config.thermal_config, Path::new(thermal::HWMON_PATH)),
+    };
 
     let duration = &config.duration_per_core;
 
@@ -70,18 +296,117 @@ pub fn run(app_state: AppState, config: &CpuTestConfig) {
 
     let cores_to_test = &config.cores_to_test;
 
-    // Print total duration
-    println!(
-        "\nTotal duration will be: {}\n",
-        pretty_print(time_to_test_per_core.mul(cores_to_test.len() as u32))
-    );
+    let all_core_duration = if config.all_core_config.enabled {
+        parse_duration::parse(&config.all_core_config.duration).unwrap()
+    } else {
+        Duration::ZERO
+    };
 
-    test_cores(
-        &config.test_methods,
-        cores_to_test.clone(),
-        time_to_test_per_core,
-        app_state,
-    )
+    let load_sweep_status = if config.load_sweep_config.enabled {
+        let time_per_step =
+            parse_duration::parse(&config.load_sweep_config.duration_per_step).unwrap();
+        println!(
+            "\nEach set of cores will be tested for: {}\n",
+            pretty_print(time_per_step)
+        );
+
+        Some(test_load_sweep(
+            &config.test_methods,
+            cores_to_test,
+            time_per_step,
+            &test_programs,
+            &app_state,
+        ))
+    } else {
+        // Print total duration
+        println!(
+            "\nTotal duration will be: {}\n",
+            pretty_print(time_to_test_per_core.mul(cores_to_test.len() as u32) + all_core_duration)
+        );
+
+        test_cores(
+            &config.test_methods,
+            cores_to_test.clone(),
+            time_to_test_per_core,
+            &test_programs,
+            &config.offset_per_core,
+            config.offset_model,
+            app_state.clone(),
+        );
+        None
+    };
+
+    // The per-core tests are done, load all cores together with all offsets applied
+    if config.all_core_config.enabled && !*app_state.terminated_by_user.read().unwrap() {
+        test_all_cores(
+            &config.test_methods,
+            cores_to_test,
+            all_core_duration,
+            &test_programs,
+            &app_state,
+        );
+    }
+
+    *app_state.tested_offsets.write().unwrap() = Some(config.offset_per_core.clone());
+    finish(&app_state, load_sweep_status);
+}
+
+/// Moves the run to Finished and reports the results
+/// Also used if the run is stopped before it started, e.g. while applying the offsets
+pub fn finish(app_state: &AppState, load_sweep_status: Option<LoadSweepStatus>) {
+    // The locks may be poisoned if the run panicked while holding them
+    let mut test_status: Vec<CpuTestStatus> = app_state
+        .test_status
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .values()
+        .cloned()
+        .collect();
+    test_status.sort_by_key(|cpu_test_status| cpu_test_status.core_id);
+    if let Err(run_state) = app_state.move_run_state(RunState::Finished) {
+        println!("Run finished in unexpected state {:?}", run_state);
+    }
+    let all_core_status = app_state
+        .all_core_status
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    app_state.events.emit(TestEvent::RunFinished {
+        stopped_by_user: *app_state
+            .terminated_by_user
+            .read()
+            .unwrap_or_else(PoisonError::into_inner),
+        test_status,
+        all_core_status,
+        load_sweep_status,
+    });
+}
+
+/// Finishes the run if it panics, otherwise it would stay active and block any further run
+struct FinishOnPanic<'a>(&'a AppState);
+
+impl Drop for FinishOnPanic<'_> {
+    fn drop(&mut self) {
+        if !thread::panicking() {
+            return;
+        }
+
+        println!("The test run panicked, stopping the test programs");
+        let app_state = self.0;
+        *app_state
+            .terminated_by_user
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = true;
+        process::resume();
+        process::kill();
+
+        // The run may have panicked in any state, e.g. while paused
+        *app_state
+            .run_state
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = RunState::Stopping;
+        finish(app_state, None);
+    }
 }
 
 /// Initializes the test results with the given configuration
@@ -95,6 +420,11 @@ pub fn initialize_response(
     // Remove all entries
     core_status.write().unwrap().clear();
 
+    // The load sweep tests sets of cores instead of single cores
+    if config.load_sweep_config.enabled {
+        return Ok(());
+    }
+
     for core_id in config.cores_to_test.clone() {
         let mut test_result = CpuTestStatus {
             core_id,
@@ -109,16 +439,10 @@ pub fn initialize_response(
                 .as_secs()
                 / config.test_methods.len() as u64;
 
-            let method_response = TestMethodResponse {
-                method: *cpu_test_method,
-                state: CpuTestMethodStatus::Idle,
-                current_secs: 0,
-                total_secs: total_secs_per_method,
-            };
-
-            test_result
-                .method_response
-                .insert(*cpu_test_method, method_response);
+            test_result.method_response.insert(
+                *cpu_test_method,
+                new_method_response(*cpu_test_method, total_secs_per_method),
+            );
         }
 
         // Initialize the test results with empty values
@@ -128,6 +452,69 @@ pub fn initialize_response(
     Ok(())
 }
 
+/// Initializes the all-core stage, None if it is disabled
+pub fn initialize_all_core_response(
+    all_core_status: &Arc<RwLock<Option<AllCoreStatus>>>,
+    config: &CpuTestConfig,
+) -> Result<(), String> {
+    let all_core_config = &config.all_core_config;
+    if !all_core_config.enabled {
+        *all_core_status.write().unwrap() = None;
+        return Ok(());
+    }
+
+    let test_time = parse_duration::parse(&all_core_config.duration)
+        .map_err(|e| format!("Error parsing duration: {}. Examples: 1h, 30m, 10s", e))?;
+
+    *all_core_status.write().unwrap() = Some(new_all_core_status(
+        "All cores".to_string(),
+        &config.cores_to_test,
+        &config.test_methods,
+        test_time,
+    ));
+
+    Ok(())
+}
+
+/// Creates the status of cores tested together, e.g. the all-core stage or a set of the load sweep
+fn new_all_core_status(
+    name: String,
+    core_ids: &[usize],
+    cpu_test_methods: &[CpuTestMethod],
+    test_time: Duration,
+) -> AllCoreStatus {
+    // Rejected by the config validation, but must not panic while the run is preparing
+    let total_secs_per_method = test_time.as_secs() / cpu_test_methods.len().max(1) as u64;
+
+    AllCoreStatus {
+        name,
+        core_ids: core_ids.to_vec(),
+        method_response: cpu_test_methods
+            .iter()
+            .map(|method| (*method, new_method_response(*method, total_secs_per_method)))
+            .collect(),
+        failed_core_ids: vec![],
+    }
+}
+
+fn new_method_response(cpu_test_method: CpuTestMethod, total_secs: u64) -> TestMethodResponse {
+    TestMethodResponse {
+        method: cpu_test_method,
+        state: CpuTestMethodStatus::Idle,
+        current_secs: 0,
+        total_secs,
+        error: None,
+        prime95_progress: match cpu_test_method {
+            CpuTestMethod::Prime95 => Some(Prime95Progress::default()),
+            _ => None,
+        },
+        wake_latency: match cpu_test_method {
+            CpuTestMethod::IdleWake => Some(WakeLatency::default()),
+            _ => None,
+        },
+    }
+}
+
 /// Pretty prints the duration in a human-readable format
 fn pretty_print(duration: Duration) -> String {
     let seconds = duration.as_secs();
@@ -182,9 +569,12 @@ fn test_get_cores_to_test() {
 }
 
 fn test_cores(
-    cpu_test_methods: &Vec<CpuTestMethod>,
+    cpu_test_methods: &[CpuTestMethod],
     core_ids: Vec<usize>,
     time_to_test_per_core: Duration,
+    test_programs: &TestPrograms,
+    offset_per_core: &HashMap<usize, i32>,
+    offset_model: OffsetModel,
     app_state: AppState,
 ) {
     for core_id in core_ids {
@@ -196,28 +586,64 @@ fn test_cores(
         );
         println!("===================================");
 
-        for cpu_test_method in cpu_test_methods {
+        let cpu_test_status = app_state.test_status.read().unwrap()[&core_id].clone();
+        app_state
+            .events
+            .emit(TestEvent::CoreStarted(cpu_test_status));
+
+        for (method_index, cpu_test_method) in cpu_test_methods.iter().enumerate() {
             let time_per_method = time_to_test_per_core.div(cpu_test_methods.len() as u32);
 
+            // Do not start the next method while paused
+            wait_while_paused(&app_state);
+
+            // The user skipped the remaining methods of the core
+            if *app_state.skip_request.read().unwrap() == Some(SkipTarget::Core) {
+                set_test_method_status(
+                    app_state.clone(),
+                    core_id,
+                    cpu_test_method,
+                    CpuTestMethodStatus::Skipped,
+                );
+                if method_index == cpu_test_methods.len() - 1 {
+                    record_in_ledger(&app_state, core_id, offset_per_core, offset_model);
+                }
+                continue;
+            }
+
             println!(
                 " --> Testing method {:?} for {:?}",
                 cpu_test_method, time_per_method
             );
 
             // Test the core for the given method
-            test_core_with_method(cpu_test_method, core_id, time_per_method, app_state.clone());
+            test_core_with_method(
+                cpu_test_method,
+                core_id,
+                time_per_method,
+                test_programs,
+                app_state.clone(),
+            );
+
+            // Record the result as soon as the core is done, before cooling down
+            let interrupted = should_interrupt(app_state.clone(), core_id);
+            if interrupted || method_index == cpu_test_methods.len() - 1 {
+                record_in_ledger(&app_state, core_id, offset_per_core, offset_model);
+            }
 
             // If cpu test result is failed, break earls the loop,
             // we do not need to test the other methods
-            if should_interrupt(app_state.clone(), core_id) {
+            if interrupted {
                 println!(" ### Test interrupted by user");
                 break;
             }
 
-            // Wait 5 seconds to cool down the CPU
-            thread::sleep(Duration::from_secs(5));
+            cool_down(Some(core_id), &test_programs.thermal_guard, &app_state);
         }
 
+        // The skip of the core is done
+        app_state.skip_request.write().unwrap().take();
+
         // Test if interrupted by user, if so cancel the whole test
         if *app_state.terminated_by_user.read().unwrap() {
             println!("Test interrupted by user");
@@ -226,6 +652,482 @@ fn test_cores(
     }
 }
 
+/// Loads all tested cores at once with each method
+/// Cores that pass alone can still fail together, e.g. due to the higher package power and temperature
+fn test_all_cores(
+    cpu_test_methods: &[CpuTestMethod],
+    core_ids: &[usize],
+    test_time: Duration,
+    test_programs: &TestPrograms,
+    app_state: &AppState,
+) {
+    println!("===================================");
+    println!(
+        "Testing cores {:?} together for {} seconds",
+        core_ids,
+        test_time.as_secs()
+    );
+    println!("===================================");
+
+    for (method_index, cpu_test_method) in cpu_test_methods.iter().enumerate() {
+        let time_per_method = test_time.div(cpu_test_methods.len().max(1) as u32);
+
+        // Do not start the next method while paused
+        wait_while_paused(app_state);
+
+        if *app_state.terminated_by_user.read().unwrap() {
+            break;
+        }
+
+        // The user skipped the remaining methods of the stage
+        if *app_state.skip_request.read().unwrap() == Some(SkipTarget::Core) {
+            update_all_core_status(app_state, |status| {
+                status.method_response_mut(cpu_test_method).state = CpuTestMethodStatus::Skipped
+            });
+            continue;
+        }
+
+        println!(
+            " --> Testing method {:?} on all cores for {:?}",
+            cpu_test_method, time_per_method
+        );
+        test_all_cores_with_method(
+            *cpu_test_method,
+            core_ids,
+            time_per_method,
+            test_programs,
+            app_state,
+        );
+
+        // Like for a single core, the remaining methods are not needed once a core failed
+        let failed = app_state
+            .all_core_status
+            .read()
+            .unwrap()
+            .as_ref()
+            .and_then(|status| status.passed())
+            == Some(false);
+        if failed || *app_state.terminated_by_user.read().unwrap() {
+            break;
+        }
+
+        if method_index < cpu_test_methods.len() - 1 {
+            cool_down(None, &test_programs.thermal_guard, app_state);
+        }
+    }
+
+    // The skip of the stage is done
+    app_state.skip_request.write().unwrap().take();
+}
+
+/// Loads growing sets of the cores per L3 group and bisects a failed set down to the offending core
+/// Each set is tested like the all-core stage and shown as its own entry
+fn test_load_sweep(
+    cpu_test_methods: &[CpuTestMethod],
+    core_ids: &[usize],
+    time_per_step: Duration,
+    test_programs: &TestPrograms,
+    app_state: &AppState,
+) -> LoadSweepStatus {
+    // Restored once the sweep is done, it is still needed for the all-core stage
+    let stage_status = app_state.all_core_status.read().unwrap().clone();
+    let mut load_sweep_status = LoadSweepStatus::default();
+
+    // Rejected by the config validation, without methods no set could fail
+    if cpu_test_methods.is_empty() {
+        return load_sweep_status;
+    }
+
+    for (l3_group, group_core_ids) in cpu_info::get_l3_groups().iter().enumerate() {
+        let group_core_ids: Vec<usize> = group_core_ids
+            .iter()
+            .copied()
+            .filter(|core_id| core_ids.contains(core_id))
+            .collect();
+        if group_core_ids.is_empty() {
+            continue;
+        }
+        if *app_state.terminated_by_user.read().unwrap() {
+            break;
+        }
+
+        let mut test_set = |set: &[usize]| {
+            if *app_state.terminated_by_user.read().unwrap() {
+                return SetOutcome::Stopped;
+            }
+
+            let core_list: Vec<String> = set.iter().map(|core_id| core_id.to_string()).collect();
+            let status = new_all_core_status(
+                format!("L3 group {}: cores {}", l3_group, core_list.join(", ")),
+                set,
+                cpu_test_methods,
+                time_per_step,
+            );
+            *app_state.all_core_status.write().unwrap() = Some(status.clone());
+            app_state.events.emit(TestEvent::AllCoreProgress(status));
+
+            test_all_cores(
+                cpu_test_methods,
+                set,
+                time_per_step,
+                test_programs,
+                app_state,
+            );
+
+            let status = app_state.all_core_status.read().unwrap().clone().unwrap();
+            let outcome = SetOutcome::of(&status, *app_state.terminated_by_user.read().unwrap());
+            load_sweep_status.steps.push(status);
+
+            cool_down(None, &test_programs.thermal_guard, app_state);
+            outcome
+        };
+
+        if let Some(finding) = load_sweep::sweep_l3_group(l3_group, &group_core_ids, &mut test_set)
+        {
+            println!(
+                "L3 group {} failed with cores {:?} loaded, offending cores {:?}",
+                finding.l3_group, finding.failed_set, finding.offending_core_ids
+            );
+            load_sweep_status.findings.push(finding);
+        }
+    }
+
+    *app_state.all_core_status.write().unwrap() = stage_status;
+    load_sweep_status
+}
+
+/// Runs the method on all cores at once until the time is up, any core failed or the user stopped
+fn test_all_cores_with_method(
+    cpu_test_method: CpuTestMethod,
+    core_ids: &[usize],
+    test_time: Duration,
+    test_programs: &TestPrograms,
+    app_state: &AppState,
+) {
+    update_all_core_status(app_state, |status| {
+        status.method_response_mut(&cpu_test_method).state = CpuTestMethodStatus::Testing
+    });
+
+    // Each core runs its own test program instance, they share the memory
+    let test_programs = TestPrograms {
+        ycruncher_config: ycruncher::config_per_instance(
+            &test_programs.ycruncher_config,
+            core_ids.len(),
+        ),
+        ..test_programs.clone()
+    };
+
+    // Set by the workers if a core failed, stops all of them
+    let time_up = Arc::new(RwLock::new(false));
+
+    let workers: Vec<_> = core_ids
+        .iter()
+        .map(|&core_id| {
+            let test_programs = test_programs.clone();
+            let time_up = time_up.clone();
+            let app_state = app_state.clone();
+            thread::Builder::new()
+                .name(format!("all_core_thread_{}", core_id))
+                .spawn(move || {
+                    test_core_in_all_core_stage(
+                        cpu_test_method,
+                        core_id,
+                        &test_programs,
+                        &time_up,
+                        &app_state,
+                    )
+                })
+                .unwrap()
+        })
+        .collect();
+
+    // Wait a bit for the test program processes to start
+    thread::sleep(Duration::from_secs(3));
+
+    let mut start_time = Utc::now();
+    let mut end_time = start_time + test_time;
+    let mut paused_since: Option<DateTime<Utc>> = None;
+
+    loop {
+        // Freeze the timer while paused, the time is added once resumed
+        if app_state.run_state() == RunState::Paused {
+            paused_since.get_or_insert_with(Utc::now);
+            thread::sleep(Duration::from_millis(500));
+            continue;
+        }
+        if let Some(paused_since) = paused_since.take() {
+            let paused = Utc::now() - paused_since;
+            start_time += paused;
+            end_time += paused;
+        }
+
+        let stopped = app_state.skip_request.read().unwrap().is_some()
+            || *app_state.terminated_by_user.read().unwrap()
+            || *time_up.read().unwrap();
+        if stopped || Utc::now() > end_time {
+            break;
+        }
+
+        // Abort the method if the CPU got too hot, this says nothing about its stability
+        if let Some(error) = thermal_abort_error(&test_programs.thermal_guard) {
+            println!("Thermal abort for all cores: {}", error);
+            update_all_core_status(app_state, |status| {
+                let method_response = status.method_response_mut(&cpu_test_method);
+                method_response.state = CpuTestMethodStatus::ThermalAbort;
+                method_response.error = Some(error);
+            });
+            break;
+        }
+
+        let current_secs = (Utc::now() - start_time).num_seconds() as u64;
+        update_all_core_status(app_state, |status| {
+            status.method_response_mut(&cpu_test_method).current_secs = current_secs
+        });
+
+        thread::sleep(Duration::from_secs(1));
+    }
+
+    // Stop the built-in methods and kill the test programs
+    *time_up.write().unwrap() = true;
+    process::kill();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+
+    // Keep the FAILED, ERROR or THERMAL ABORT state set while the method ran
+    let terminated_by_user = *app_state.terminated_by_user.read().unwrap();
+    let skip_request = app_state.skip_request.read().unwrap().to_owned();
+    if skip_request == Some(SkipTarget::Method) {
+        app_state.skip_request.write().unwrap().take();
+    }
+    update_all_core_status(app_state, |status| {
+        let method_response = status.method_response_mut(&cpu_test_method);
+        if method_response.state != CpuTestMethodStatus::Testing {
+            return;
+        }
+        method_response.state = if terminated_by_user {
+            CpuTestMethodStatus::Idle
+        } else if skip_request.is_some() {
+            CpuTestMethodStatus::Skipped
+        } else {
+            CpuTestMethodStatus::Success
+        };
+    });
+}
+
+/// Returns the error of a thermal abort if the CPU is above the max temperature
+fn thermal_abort_error(thermal_guard: &ThermalGuard) -> Option<String> {
+    thermal_guard.exceeded_temperature().map(|temperature| {
+        format!(
+            "Aborted at {:.1} °C, above the max temperature of {} °C",
+            temperature, thermal_guard.config.max_temperature
+        )
+    })
+}
+
+/// Runs the method on one core of the all-core stage until the time is up
+fn test_core_in_all_core_stage(
+    cpu_test_method: CpuTestMethod,
+    core_id: usize,
+    test_programs: &TestPrograms,
+    time_up: &Arc<RwLock<bool>>,
+    app_state: &AppState,
+) {
+    let child = match cpu_test_method {
+        CpuTestMethod::Prime95 => {
+            mprime::start_verification(core_id, &test_programs.prime95_binary)
+        }
+        CpuTestMethod::YCruncher => ycruncher::start_verification(
+            core_id,
+            &test_programs.ycruncher_binary,
+            &test_programs.ycruncher_config,
+        ),
+        CpuTestMethod::BoostTransition | CpuTestMethod::StressKernel | CpuTestMethod::IdleWake => {
+            let control = StageControl {
+                time_up: time_up.clone(),
+                app_state: app_state.clone(),
+            };
+            let result = match cpu_test_method {
+                CpuTestMethod::BoostTransition => {
+                    run_pinned(core_id, &control, boost_transition::run)
+                }
+                CpuTestMethod::StressKernel => run_pinned(core_id, &control, stress_kernel::run),
+                _ => run_pinned(core_id, &control, |core_id, control| {
+                    idle_wake::run(core_id, waker_core_for(core_id), control, |_| {})
+                }),
+            };
+            match result {
+                Ok(()) => {}
+                Err(BuiltinFailure::Verification(message)) => {
+                    report_all_core_failure(app_state, cpu_test_method, core_id, message, time_up)
+                }
+                Err(BuiltinFailure::Error(error)) => {
+                    report_all_core_error(app_state, cpu_test_method, core_id, error, time_up)
+                }
+            }
+            return;
+        }
+    };
+
+    let mut child = match child {
+        Ok(child) => child,
+        Err(error) => {
+            report_all_core_error(app_state, cpu_test_method, core_id, error, time_up);
+            return;
+        }
+    };
+
+    let stdout = child.stdout.take().unwrap();
+    let mut lines = std::io::BufReader::new(stdout)
+        .lines()
+        .map_while(Result::ok);
+
+    // Make sure y-cruncher is actually running the configured stress test
+    if cpu_test_method == CpuTestMethod::YCruncher {
+        if let Err(error) =
+            ycruncher::wait_for_stress_start(&mut lines, &test_programs.ycruncher_config)
+        {
+            // If the time is up, the process was killed by us
+            if !*time_up.read().unwrap() {
+                report_all_core_error(app_state, cpu_test_method, core_id, error, time_up);
+            }
+            return;
+        }
+    }
+
+    for line in lines {
+        if *time_up.read().unwrap() {
+            break;
+        }
+
+        if line.contains(mprime::ERROR_MESSAGE) || line.contains(ycruncher::ERROR_MESSAGE) {
+            let message = format!("{} reported an error", cpu_test_method);
+            report_all_core_failure(app_state, cpu_test_method, core_id, message, time_up);
+            break;
+        }
+    }
+
+    // The stage kills the process once the time is up
+    process::wait(child);
+}
+
+/// Attributes the failure to the core and stops the other cores
+fn report_all_core_failure(
+    app_state: &AppState,
+    cpu_test_method: CpuTestMethod,
+    core_id: usize,
+    message: String,
+    time_up: &Arc<RwLock<bool>>,
+) {
+    println!("#############");
+    println!(
+        "Verification failed for core {} in the all-core stage",
+        core_id
+    );
+    println!("#############");
+
+    update_all_core_status(app_state, |status| {
+        status.add_failed_core(core_id);
+        let method_response = status.method_response_mut(&cpu_test_method);
+        method_response.state = CpuTestMethodStatus::Failed;
+        method_response.error = Some(format!("Core {}: {}", core_id, message));
+    });
+    *time_up.write().unwrap() = true;
+}
+
+/// Marks the method as not run as expected, unless a core already failed
+fn report_all_core_error(
+    app_state: &AppState,
+    cpu_test_method: CpuTestMethod,
+    core_id: usize,
+    error: String,
+    time_up: &Arc<RwLock<bool>>,
+) {
+    println!(
+        "Error for core {} in the all-core stage: {}",
+        core_id, error
+    );
+
+    update_all_core_status(app_state, |status| {
+        let method_response = status.method_response_mut(&cpu_test_method);
+        if method_response.state != CpuTestMethodStatus::Failed {
+            method_response.state = CpuTestMethodStatus::Error;
+            method_response.error = Some(format!("Core {}: {}", core_id, error));
+        }
+    });
+    *time_up.write().unwrap() = true;
+}
+
+/// Updates the all-core stage and pushes it to the UI
+fn update_all_core_status(app_state: &AppState, update: impl FnOnce(&mut AllCoreStatus)) {
+    let mut all_core_status = app_state.all_core_status.write().unwrap();
+    let Some(status) = all_core_status.as_mut() else {
+        return;
+    };
+    update(status);
+    let status = status.clone();
+
+    // Important, do not hold the lock while emitting
+    drop(all_core_status);
+    app_state.events.emit(TestEvent::AllCoreProgress(status));
+}
+
+/// Waits until the CPU cooled down below the cooldown temperature, but at least 5 seconds
+fn cool_down(core_id: Option<usize>, thermal_guard: &ThermalGuard, app_state: &AppState) {
+    thread::sleep(Duration::from_secs(5));
+
+    let start_time = Instant::now();
+    while let Some(temperature) = thermal_guard.cooldown_pending() {
+        if *app_state.terminated_by_user.read().unwrap() {
+            return;
+        }
+
+        // The cooldown temperature may never be reached, e.g. if it is below the idle temperature
+        if start_time.elapsed() > thermal::MAX_COOLDOWN_TIME {
+            println!(
+                " --> Did not cool down to {} °C within {}, continuing at {:.1} °C",
+                thermal_guard.config.cooldown_temperature,
+                pretty_print(thermal::MAX_COOLDOWN_TIME),
+                temperature
+            );
+            return;
+        }
+
+        println!(
+            " --> Cooling down from {:.1} °C to {} °C",
+            temperature, thermal_guard.config.cooldown_temperature
+        );
+        app_state.events.emit(TestEvent::Cooldown {
+            core_id,
+            temperature,
+            target: thermal_guard.config.cooldown_temperature,
+        });
+        thread::sleep(Duration::from_secs(1));
+    }
+}
+
+/// Blocks until the run is resumed, returns immediately if it is not paused
+fn wait_while_paused(app_state: &AppState) {
+    while app_state.run_state() == RunState::Paused {
+        thread::sleep(Duration::from_millis(500));
+    }
+}
+
+/// Adds the result of the core to the stability ledger
+fn record_in_ledger(
+    app_state: &AppState,
+    core_id: usize,
+    offset_per_core: &HashMap<usize, i32>,
+    offset_model: OffsetModel,
+) {
+    let cpu_test_status = app_state.test_status.read().unwrap()[&core_id].clone();
+    let offset = offset_per_core.get(&core_id).copied().unwrap_or(0);
+
+    if let Err(error) = ledger::record_core(&cpu_test_status, offset, offset_model) {
+        println!("Failed to update the ledger: {}", error);
+    }
+}
+
 /// Checks if the test should be interrupted
 /// This could be due to any test failing or the user stopping the test
 fn should_interrupt(app_state: AppState, core_id: usize) -> bool {
@@ -240,6 +1142,31 @@ fn set_test_method_status(
     core_id: usize,
     cpu_test_method: &CpuTestMethod,
     status: CpuTestMethodStatus,
+) {
+    update_method_response(&app_state, core_id, cpu_test_method, |method_response| {
+        method_response.state = status;
+    });
+}
+
+/// Updates the response of the method and pushes the change to the UI
+fn update_method_response(
+    app_state: &AppState,
+    core_id: usize,
+    cpu_test_method: &CpuTestMethod,
+    update: impl FnOnce(&mut TestMethodResponse),
+) {
+    update_method_response_if_changed(app_state, core_id, cpu_test_method, |method_response| {
+        update(method_response);
+        true
+    });
+}
+
+/// Like update_method_response, but only emits the progress if the update returns true
+fn update_method_response_if_changed(
+    app_state: &AppState,
+    core_id: usize,
+    cpu_test_method: &CpuTestMethod,
+    update: impl FnOnce(&mut TestMethodResponse) -> bool,
 ) {
     let mut test_status = app_state.test_status.write().unwrap();
     let test_result = test_status.get_mut(&core_id).unwrap();
@@ -247,13 +1174,42 @@ fn set_test_method_status(
         .method_response
         .get_mut(cpu_test_method)
         .unwrap();
-    method_response.state = status;
+    if !update(method_response) {
+        return;
+    }
+    let method_response = method_response.clone();
+
+    // Important, do not hold the lock while emitting
+    drop(test_status);
+
+    app_state.events.emit(TestEvent::MethodProgress {
+        core_id,
+        method_response,
+    });
+}
+
+/// Marks the method as not executable, e.g. if the test program did not start as expected
+fn set_test_method_error(
+    app_state: AppState,
+    core_id: usize,
+    cpu_test_method: &CpuTestMethod,
+    error: String,
+) {
+    println!("#############");
+    println!("Test program error for core {}: {}", core_id, error);
+    println!("#############");
+
+    update_method_response(&app_state, core_id, cpu_test_method, |method_response| {
+        method_response.state = CpuTestMethodStatus::Error;
+        method_response.error = Some(error);
+    });
 }
 
 fn test_core_with_method(
     cpu_test_method: &CpuTestMethod,
     core_id: usize,
     test_time: Duration,
+    test_programs: &TestPrograms,
     app_state: AppState,
 ) {
     // Set the state of the method to TESTING
@@ -273,14 +1229,20 @@ fn test_core_with_method(
     let pid_for_core_test = pid.clone();
     let test_program_process_for_core_test = test_program_process.clone();
     let cpu_test_method = *cpu_test_method;
+    let test_programs_for_core_test = test_programs.clone();
+    let time_up_for_core_test = time_up.clone();
+    let app_state_for_core_test = app_state.clone();
     let test_program_thread = thread::Builder::new()
         .name(format!("test_program_thread_{}", core_id))
         .spawn(move || {
             start_test_program_for_core(
                 cpu_test_method,
                 core_id,
+                &test_programs_for_core_test,
                 pid_for_core_test,
                 test_program_process_for_core_test,
+                time_up_for_core_test,
+                app_state_for_core_test,
             );
         })
         .unwrap();
@@ -304,11 +1266,14 @@ fn test_core_with_method(
     let time_up_for_monitor_process = time_up.clone();
     let app_state_for_monitor_process = app_state.clone();
     let mprime_process_for_monitor_process = test_program_process.clone();
+    let ycruncher_config_for_monitor_process = test_programs.ycruncher_config.clone();
     let monitor_process_thread = thread::Builder::new()
         .name(format!("monitor_process_thread_{}", core_id))
         .spawn(move || {
             monitor_process(
                 core_id,
+                cpu_test_method,
+                &ycruncher_config_for_monitor_process,
                 time_up_for_monitor_process,
                 app_state_for_monitor_process,
                 mprime_process_for_monitor_process,
@@ -319,6 +1284,7 @@ fn test_core_with_method(
     // Thread that checks if the time to test per core has passed
     let time_up_for_time_tester = time_up.clone();
     let app_state_for_time_tester = app_state.clone();
+    let thermal_guard_for_time_tester = test_programs.thermal_guard.clone();
     let core_test_timer_thread = thread::Builder::new()
         .name(format!("core_test_timer_thread_{}", core_id))
         .spawn(move || {
@@ -327,6 +1293,7 @@ fn test_core_with_method(
                 &cpu_test_method,
                 start_time,
                 end_time,
+                &thermal_guard_for_time_tester,
                 time_up_for_time_tester,
                 app_state_for_time_tester,
             );
@@ -339,6 +1306,36 @@ fn test_core_with_method(
     monitor_process_thread.join().unwrap();
     core_test_timer_thread.join().unwrap();
 
+    // The timer killed the test program, reap it so its pid is no longer signalled
+    if let Some(child) = test_program_process.write().unwrap().take() {
+        process::wait(child);
+    }
+
+    // Keep the ERROR or THERMAL ABORT state if the test program did not run as expected
+    let state =
+        app_state.test_status.read().unwrap()[&core_id].method_response[&cpu_test_method].state;
+    if matches!(
+        state,
+        CpuTestMethodStatus::Error | CpuTestMethodStatus::ThermalAbort
+    ) {
+        return;
+    }
+
+    // Keep the skipped method from being counted as passed
+    let skip_request = *app_state.skip_request.read().unwrap();
+    if let Some(skip_target) = skip_request {
+        if skip_target == SkipTarget::Method {
+            app_state.skip_request.write().unwrap().take();
+        }
+        set_test_method_status(
+            app_state.clone(),
+            core_id,
+            &cpu_test_method,
+            CpuTestMethodStatus::Skipped,
+        );
+        return;
+    }
+
     // Set the state of the method to SUCCESS if the verification did not fail
     if !app_state.test_status.read().unwrap()[&core_id].verification_failed {
         set_test_method_status(
@@ -387,12 +1384,61 @@ fn dedup<T: Eq + Hash + Copy>(v: &mut Vec<T>) {
 fn check_time_left(
     physical_core_id: usize,
     cpu_test_method: &CpuTestMethod,
-    start_time: DateTime<Utc>,
-    end_time: DateTime<Utc>,
+    mut start_time: DateTime<Utc>,
+    mut end_time: DateTime<Utc>,
+    thermal_guard: &ThermalGuard,
     time_up: Arc<RwLock<bool>>,
     app_state: AppState,
 ) {
+    let mut paused_since: Option<DateTime<Utc>> = None;
+
     loop {
+        // Freeze the timer while paused, the time is added once resumed
+        if app_state.run_state() == RunState::Paused {
+            paused_since.get_or_insert_with(Utc::now);
+            thread::sleep(Duration::from_millis(500));
+            continue;
+        }
+        if let Some(paused_since) = paused_since.take() {
+            let paused = Utc::now() - paused_since;
+            start_time += paused;
+            end_time += paused;
+        }
+
+        // Check if the user skipped the method or the core
+        if app_state.skip_request.read().unwrap().is_some() {
+            println!("Skipped by user");
+            *time_up.write().unwrap() = true;
+            process::kill();
+
+            break;
+        }
+
+        // Abort the method if the CPU got too hot, this says nothing about its stability
+        if let Some(error) = thermal_abort_error(thermal_guard) {
+            println!("Thermal abort for core {}: {}", physical_core_id, error);
+            update_method_response(
+                &app_state,
+                physical_core_id,
+                cpu_test_method,
+                |method_response| {
+                    method_response.state = CpuTestMethodStatus::ThermalAbort;
+                    method_response.error = Some(error);
+                },
+            );
+            *time_up.write().unwrap() = true;
+            process::kill();
+
+            break;
+        }
+
+        // Check if the test was aborted, e.g. due to a test program error
+        if *time_up.read().unwrap() {
+            process::kill();
+
+            break;
+        }
+
         // Check if the time is up
         if Utc::now() > end_time {
             println!("Time is up");
@@ -414,16 +1460,13 @@ fn check_time_left(
         }
 
         // Update current_secs
-        let mut core_status = app_state.test_status.write().unwrap();
-        let cpu_test_status = core_status.get_mut(&physical_core_id).unwrap();
-        let method_response = cpu_test_status
-            .method_response
-            .get_mut(cpu_test_method)
-            .unwrap();
-        method_response.current_secs = (Utc::now() - start_time).num_seconds() as u64;
-
-        // Important, drop the lock before sleeping
-        drop(core_status);
+        let current_secs = (Utc::now() - start_time).num_seconds() as u64;
+        update_method_response(
+            &app_state,
+            physical_core_id,
+            cpu_test_method,
+            |method_response| method_response.current_secs = current_secs,
+        );
 
         // Wait a second
         thread::sleep(Duration::from_secs(1));
@@ -433,12 +1476,63 @@ fn check_time_left(
 fn start_test_program_for_core(
     cpu_test_method: CpuTestMethod,
     physical_core_id: usize,
+    test_programs: &TestPrograms,
     pid: Arc<RwLock<u32>>,
     test_program_process: Arc<RwLock<Option<Child>>>,
+    time_up: Arc<RwLock<bool>>,
+    app_state: AppState,
 ) {
     let child = match cpu_test_method {
-        CpuTestMethod::Prime95 => mprime::start_verification(physical_core_id),
-        CpuTestMethod::YCruncher => ycruncher::start_verification(physical_core_id),
+        CpuTestMethod::Prime95 => {
+            mprime::start_verification(physical_core_id, &test_programs.prime95_binary)
+        }
+        CpuTestMethod::YCruncher => ycruncher::start_verification(
+            physical_core_id,
+            &test_programs.ycruncher_binary,
+            &test_programs.ycruncher_config,
+        ),
+        CpuTestMethod::BoostTransition => {
+            let control = RunControl {
+                physical_core_id,
+                time_up,
+                app_state,
+            };
+            run_builtin_method(cpu_test_method, &control, boost_transition::run);
+            return;
+        }
+        CpuTestMethod::StressKernel => {
+            let control = RunControl {
+                physical_core_id,
+                time_up,
+                app_state,
+            };
+            run_builtin_method(cpu_test_method, &control, stress_kernel::run);
+            return;
+        }
+        CpuTestMethod::IdleWake => {
+            let waker_core_id = waker_core_for(physical_core_id);
+            let control = RunControl {
+                physical_core_id,
+                time_up,
+                app_state: app_state.clone(),
+            };
+            run_builtin_method(cpu_test_method, &control, |physical_core_id, control| {
+                idle_wake::run(physical_core_id, waker_core_id, control, |wake_latency| {
+                    update_wake_latency(&app_state, physical_core_id, wake_latency)
+                })
+            });
+            return;
+        }
+    };
+
+    // Abort the method if the test program could not be started
+    let child = match child {
+        Ok(child) => child,
+        Err(error) => {
+            set_test_method_error(app_state, physical_core_id, &cpu_test_method, error);
+            *time_up.write().unwrap() = true;
+            return;
+        }
     };
 
     // Set the pid of the child process
@@ -448,6 +1542,88 @@ fn start_test_program_for_core(
     *test_program_process.write().unwrap() = Some(child);
 }
 
+/// Stops or pauses a built-in method together with the run
+struct RunControl {
+    physical_core_id: usize,
+    time_up: Arc<RwLock<bool>>,
+    app_state: AppState,
+}
+
+impl MethodControl for RunControl {
+    fn should_stop(&self) -> bool {
+        *self.time_up.read().unwrap()
+            || should_interrupt(self.app_state.clone(), self.physical_core_id)
+    }
+
+    fn is_paused(&self) -> bool {
+        self.app_state.run_state() == RunState::Paused
+    }
+}
+
+/// Stops or pauses a built-in method together with the all-core stage
+struct StageControl {
+    time_up: Arc<RwLock<bool>>,
+    app_state: AppState,
+}
+
+impl MethodControl for StageControl {
+    fn should_stop(&self) -> bool {
+        *self.time_up.read().unwrap()
+    }
+
+    fn is_paused(&self) -> bool {
+        self.app_state.run_state() == RunState::Paused
+    }
+}
+
+/// Runs a built-in method on the current thread until the time is up and reports its failures
+fn run_builtin_method(
+    cpu_test_method: CpuTestMethod,
+    control: &RunControl,
+    run: impl FnOnce(usize, &dyn MethodControl) -> Result<(), BuiltinFailure>,
+) {
+    let physical_core_id = control.physical_core_id;
+
+    match run_pinned(physical_core_id, control, run) {
+        Ok(()) => {}
+        Err(BuiltinFailure::Verification(message)) => {
+            report_verification_failure(&control.app_state, physical_core_id, cpu_test_method);
+            update_method_response(
+                &control.app_state,
+                physical_core_id,
+                &cpu_test_method,
+                |method_response| method_response.error = Some(message),
+            );
+        }
+        Err(BuiltinFailure::Error(error)) => {
+            set_test_method_error(
+                control.app_state.clone(),
+                physical_core_id,
+                &cpu_test_method,
+                error,
+            );
+            *control.time_up.write().unwrap() = true;
+        }
+    }
+}
+
+/// Pins the current thread to the core and runs the built-in method on it
+fn run_pinned(
+    physical_core_id: usize,
+    control: &dyn MethodControl,
+    run: impl FnOnce(usize, &dyn MethodControl) -> Result<(), BuiltinFailure>,
+) -> Result<(), BuiltinFailure> {
+    process::pin_current_thread(physical_core_id)
+        .map_err(BuiltinFailure::Error)
+        .and_then(|_| run(physical_core_id, control))
+}
+
+/// The core that wakes the tested one in the idle wake test, None if the CPU has only one core
+fn waker_core_for(physical_core_id: usize) -> Option<usize> {
+    let waker_core_id = (physical_core_id + 1) % cpu_info::get_physical_cores();
+    Some(waker_core_id).filter(|waker_core_id| *waker_core_id != physical_core_id)
+}
+
 fn monitor_cpu(physical_core_id: usize, time_up: Arc<RwLock<bool>>, app_state: AppState) {
     loop {
         // Check if time is up or if the verification failed
@@ -462,11 +1638,19 @@ fn monitor_cpu(physical_core_id: usize, time_up: Arc<RwLock<bool>>, app_state: A
         // Update clocks in the test results
         let mut core_status = app_state.test_status.write().unwrap();
         let cpu_test_status = core_status.get_mut(&physical_core_id).unwrap();
+        let is_new_max_clock = current_freq > cpu_test_status.max_clock;
         cpu_test_status.max_clock = std::cmp::max(cpu_test_status.max_clock, current_freq);
 
         // Important, drop the lock before sleeping
         drop(core_status);
 
+        if is_new_max_clock {
+            app_state.events.emit(TestEvent::ClockUpdate {
+                core_id: physical_core_id,
+                max_clock: current_freq,
+            });
+        }
+
         // Wait a second
         thread::sleep(Duration::from_millis(500));
     }
@@ -474,6 +1658,8 @@ fn monitor_cpu(physical_core_id: usize, time_up: Arc<RwLock<bool>>, app_state: A
 
 fn monitor_process(
     physical_core_id: usize,
+    cpu_test_method: CpuTestMethod,
+    ycruncher_config: &YCruncherConfig,
     time_up: Arc<RwLock<bool>>,
     app_state: AppState,
     mprime_process: Arc<RwLock<Option<Child>>>,
@@ -481,7 +1667,24 @@ fn monitor_process(
     if let Some(mprime_process) = &mut *mprime_process.write().unwrap() {
         let stdout = mprime_process.stdout.as_mut().unwrap();
         let reader = std::io::BufReader::new(stdout);
-        let lines = reader.lines();
+        let mut lines = reader.lines().map(|line| line.unwrap());
+
+        // Make sure y-cruncher is actually running the configured stress test
+        if cpu_test_method == CpuTestMethod::YCruncher {
+            if let Err(error) = ycruncher::wait_for_stress_start(&mut lines, ycruncher_config) {
+                // If the time is up, the process was killed by us
+                if !*time_up.read().unwrap() {
+                    set_test_method_error(
+                        app_state.clone(),
+                        physical_core_id,
+                        &cpu_test_method,
+                        error,
+                    );
+                    *time_up.write().unwrap() = true;
+                }
+                return;
+            }
+        }
 
         for line in lines {
             // if time is up, break
@@ -489,39 +1692,457 @@ fn monitor_process(
                 break;
             }
 
-            let line = line.unwrap();
+            if cpu_test_method == CpuTestMethod::Prime95 {
+                update_prime95_progress(&app_state, physical_core_id, &line);
+            }
 
             if line.contains(mprime::ERROR_MESSAGE) || line.contains(ycruncher::ERROR_MESSAGE) {
-                println!("#############");
-                println!("Verification failed for core {}", physical_core_id);
-                println!("#############");
-
-                // Set the verification failed flag
-                let mut core_status = app_state.test_status.write().unwrap();
-                let test_result = core_status.get_mut(&physical_core_id).unwrap();
-                test_result.verification_failed = true;
-
+                report_verification_failure(&app_state, physical_core_id, cpu_test_method);
                 break;
             }
         }
     }
 }
 
+/// Marks the core as failed, which interrupts the remaining methods of the core
+fn report_verification_failure(
+    app_state: &AppState,
+    physical_core_id: usize,
+    cpu_test_method: CpuTestMethod,
+) {
+    println!("#############");
+    println!("Verification failed for core {}", physical_core_id);
+    println!("#############");
+
+    // Set the verification failed flag
+    let mut core_status = app_state.test_status.write().unwrap();
+    let test_result = core_status.get_mut(&physical_core_id).unwrap();
+    test_result.verification_failed = true;
+    drop(core_status);
+
+    app_state.events.emit(TestEvent::Failure {
+        core_id: physical_core_id,
+        method: cpu_test_method,
+    });
+}
+
+/// Updates the prime95 progress of the core with the given mprime output line
+/// Most mprime output lines do not change the progress, only changes are emitted
+fn update_prime95_progress(app_state: &AppState, physical_core_id: usize, line: &str) {
+    update_method_response_if_changed(
+        app_state,
+        physical_core_id,
+        &CpuTestMethod::Prime95,
+        |method_response| {
+            method_response
+                .prime95_progress
+                .as_mut()
+                .is_some_and(|progress| mprime::parse_progress_line(line, progress))
+        },
+    );
+}
+
+/// Updates the wake latency of the idle wake test
+fn update_wake_latency(app_state: &AppState, physical_core_id: usize, wake_latency: &WakeLatency) {
+    update_method_response(
+        app_state,
+        physical_core_id,
+        &CpuTestMethod::IdleWake,
+        |method_response| method_response.wake_latency = Some(wake_latency.clone()),
+    );
+}
+
 pub fn stop(cpu_test_status: AppState) {
     // Set interrupted by user flag
     *cpu_test_status.terminated_by_user.write().unwrap() = true;
 
+    // Suspended processes only handle the termination once continued
+    process::resume();
+
     // Kill all processes
     process::kill();
 
     // Reset all test statuses
     let mut core_status = cpu_test_status.test_status.write().unwrap();
-    for (_, test_result) in core_status.iter_mut() {
+    let mut reset_responses = vec![];
+    for (core_id, test_result) in core_status.iter_mut() {
         for (_, method_response) in test_result.method_response.iter_mut() {
             if method_response.state == CpuTestMethodStatus::Testing {
                 method_response.state = CpuTestMethodStatus::Idle;
             }
             method_response.current_secs = 0;
+            reset_responses.push((*core_id, method_response.clone()));
         }
     }
+    drop(core_status);
+
+    for (core_id, method_response) in reset_responses {
+        cpu_test_status.events.emit(TestEvent::MethodProgress {
+            core_id,
+            method_response,
+        });
+    }
+}
+
+/// Starts preparing a run, the stop flag of the last run is reset while holding the state,
+/// so a stop of the new run can not be reset
+pub fn start(app_state: &AppState) -> Result<(), RunState> {
+    app_state.move_run_state_with(RunState::Preparing, || {
+        *app_state.terminated_by_user.write().unwrap() = false;
+    })
+}
+
+/// Suspends the test programs and freezes the timer of the running method
+/// Suspends the test programs, signalled while holding the state so a resume always follows it
+pub fn pause(app_state: &AppState) -> Result<(), RunState> {
+    app_state.move_run_state_with(RunState::Paused, process::pause)
+}
+
+pub fn resume(app_state: &AppState) -> Result<(), RunState> {
+    app_state.move_run_state_with(RunState::Running, process::resume)
+}
+
+/// Aborts the current method or core, the run continues with the next one
+/// Rejected if no method is testing, e.g. while cooling down, the request would hit the next one
+pub fn skip(app_state: &AppState, skip_target: SkipTarget) -> Result<(), RunState> {
+    let run_state = app_state.run_state();
+    if run_state != RunState::Running {
+        return Err(run_state);
+    }
+
+    let mut skip_request = app_state.skip_request.write().unwrap();
+    let is_testing = |method_response: &TestMethodResponse| {
+        method_response.state == CpuTestMethodStatus::Testing
+    };
+    let any_core_testing = app_state
+        .test_status
+        .read()
+        .unwrap()
+        .values()
+        .any(|cpu_test_status| cpu_test_status.method_response.values().any(is_testing));
+    let all_cores_testing = app_state
+        .all_core_status
+        .read()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|all_core_status| all_core_status.method_response.values().any(is_testing));
+    if !any_core_testing && !all_cores_testing {
+        return Err(run_state);
+    }
+
+    *skip_request = Some(skip_target);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn test_app_state(events: Arc<Mutex<Vec<TestEvent>>>) -> AppState {
+        let test_status = CpuTestStatus {
+            core_id: 2,
+            max_clock: 0,
+            verification_failed: false,
+            method_response: HashMap::from([(
+                CpuTestMethod::YCruncher,
+                TestMethodResponse {
+                    method: CpuTestMethod::YCruncher,
+                    state: CpuTestMethodStatus::Testing,
+                    current_secs: 0,
+                    total_secs: 60,
+                    error: None,
+                    prime95_progress: None,
+                    wake_latency: None,
+                },
+            )]),
+        };
+
+        AppState {
+            test_status: Arc::new(RwLock::new(HashMap::from([(2, test_status)]))),
+            terminated_by_user: Arc::new(RwLock::new(false)),
+            config_store: ConfigStore::new(std::env::temp_dir().join("pbo-assistant-unused.json")),
+            events: TestEventSink::new(move |event| events.lock().unwrap().push(event)),
+            run_state: Arc::new(RwLock::new(RunState::Idle)),
+            skip_request: Arc::new(RwLock::new(None)),
+            all_core_status: Arc::new(RwLock::new(None)),
+            tested_offsets: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    #[test]
+    fn update_method_response_emits_progress() {
+        // GIVEN
+        let events = Arc::new(Mutex::new(vec![]));
+        let app_state = test_app_state(events.clone());
+
+        // WHEN
+        update_method_response(
+            &app_state,
+            2,
+            &CpuTestMethod::YCruncher,
+            |method_response| method_response.current_secs = 42,
+        );
+
+        // THEN
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].name(), "method-progress");
+        assert_eq!(
+            serde_json::to_value(&events[0]).unwrap()["method_response"]["current_secs"],
+            42
+        );
+        let test_status = app_state.test_status.read().unwrap();
+        assert_eq!(
+            test_status[&2].method_response[&CpuTestMethod::YCruncher].current_secs,
+            42
+        );
+    }
+
+    #[test]
+    fn update_prime95_progress_emits_only_changes() {
+        // GIVEN
+        let events = Arc::new(Mutex::new(vec![]));
+        let app_state = test_app_state(events.clone());
+        app_state
+            .test_status
+            .write()
+            .unwrap()
+            .get_mut(&2)
+            .unwrap()
+            .method_response
+            .insert(
+                CpuTestMethod::Prime95,
+                TestMethodResponse {
+                    method: CpuTestMethod::Prime95,
+                    state: CpuTestMethodStatus::Testing,
+                    current_secs: 0,
+                    total_secs: 60,
+                    error: None,
+                    prime95_progress: Some(Prime95Progress::default()),
+                    wake_latency: None,
+                },
+            );
+        let fft_line = "Test 1, 12000 Lucas-Lehmer iterations of M21871519 using FMA3 FFT length 1120K, Pass1=448, Pass2=2560, clm=4.";
+
+        // WHEN
+        update_prime95_progress(&app_state, 2, fft_line);
+        update_prime95_progress(&app_state, 2, "[Worker #1 Jan 1 12:00] Test 1 of 12");
+        update_prime95_progress(&app_state, 2, fft_line);
+
+        // THEN
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            serde_json::to_value(&events[0]).unwrap()["method_response"]["prime95_progress"]
+                ["current_fft_length"],
+            "1120K"
+        );
+    }
+
+    #[test]
+    fn move_run_state_lifecycle() {
+        // GIVEN
+        let app_state = test_app_state(Arc::new(Mutex::new(vec![])));
+
+        // WHEN
+        let result = [
+            RunState::Preparing,
+            RunState::Running,
+            RunState::Stopping,
+            RunState::Finished,
+            RunState::Preparing,
+        ]
+        .map(|next| app_state.move_run_state(next));
+
+        // THEN
+        assert!(result.iter().all(|result| result.is_ok()));
+        assert_eq!(app_state.run_state(), RunState::Preparing);
+    }
+
+    #[test]
+    fn move_run_state_stop_while_preparing() {
+        // GIVEN
+        let app_state = test_app_state(Arc::new(Mutex::new(vec![])));
+        app_state.move_run_state(RunState::Preparing).unwrap();
+
+        // WHEN
+        let stop_result = app_state.move_run_state(RunState::Stopping);
+        let running_result = app_state.move_run_state(RunState::Running);
+
+        // THEN
+        assert!(stop_result.is_ok());
+        assert_eq!(running_result, Err(RunState::Stopping));
+    }
+
+    #[test]
+    fn finish_on_panic_finishes_run() {
+        // GIVEN
+        let events = Arc::new(Mutex::new(vec![]));
+        let app_state = test_app_state(events.clone());
+        app_state.move_run_state(RunState::Preparing).unwrap();
+        app_state.move_run_state(RunState::Running).unwrap();
+        app_state.move_run_state(RunState::Paused).unwrap();
+
+        // WHEN
+        let run_app_state = app_state.clone();
+        let result = thread::spawn(move || {
+            let _finish_on_panic = FinishOnPanic(&run_app_state);
+            panic!("Test program crashed");
+        })
+        .join();
+
+        // THEN
+        assert!(result.is_err());
+        assert_eq!(app_state.run_state(), RunState::Finished);
+        let events = events.lock().unwrap();
+        assert_eq!(events.last().unwrap().name(), "run-finished");
+    }
+
+    #[test]
+    fn move_run_state_rejects_second_run() {
+        // GIVEN
+        let app_state = test_app_state(Arc::new(Mutex::new(vec![])));
+        app_state.move_run_state(RunState::Preparing).unwrap();
+        app_state.move_run_state(RunState::Running).unwrap();
+
+        // WHEN
+        let start_result = app_state.move_run_state(RunState::Preparing);
+        let idle_result = app_state.move_run_state(RunState::Idle);
+
+        // THEN
+        assert_eq!(start_result, Err(RunState::Running));
+        assert_eq!(idle_result, Err(RunState::Running));
+        assert!(app_state.run_state().is_active());
+    }
+
+    #[test]
+    fn start_resets_stop_flag() {
+        // GIVEN
+        let app_state = test_app_state(Arc::new(Mutex::new(vec![])));
+        *app_state.terminated_by_user.write().unwrap() = true;
+
+        // WHEN
+        let start_result = start(&app_state);
+        let terminated_after_start = *app_state.terminated_by_user.read().unwrap();
+        *app_state.terminated_by_user.write().unwrap() = true;
+        let second_start_result = start(&app_state);
+
+        // THEN
+        assert!(start_result.is_ok());
+        assert!(!terminated_after_start);
+        assert_eq!(second_start_result, Err(RunState::Preparing));
+        assert!(*app_state.terminated_by_user.read().unwrap());
+    }
+
+    #[test]
+    fn move_run_state_pause_and_resume() {
+        // GIVEN
+        let app_state = test_app_state(Arc::new(Mutex::new(vec![])));
+        app_state.move_run_state(RunState::Preparing).unwrap();
+
+        // WHEN
+        let pause_before_running = app_state.move_run_state(RunState::Paused);
+        app_state.move_run_state(RunState::Running).unwrap();
+        let pause_result = app_state.move_run_state(RunState::Paused);
+        let finish_while_paused = app_state.move_run_state(RunState::Finished);
+        let resume_result = app_state.move_run_state(RunState::Running);
+
+        // THEN
+        assert_eq!(pause_before_running, Err(RunState::Preparing));
+        assert!(pause_result.is_ok());
+        assert_eq!(finish_while_paused, Err(RunState::Paused));
+        assert!(resume_result.is_ok());
+    }
+
+    #[test]
+    fn skip_only_while_running() {
+        // GIVEN
+        let app_state = test_app_state(Arc::new(Mutex::new(vec![])));
+
+        // WHEN
+        let idle_result = skip(&app_state, SkipTarget::Core);
+        app_state.move_run_state(RunState::Preparing).unwrap();
+        app_state.move_run_state(RunState::Running).unwrap();
+        let running_result = skip(&app_state, SkipTarget::Method);
+
+        // THEN
+        assert_eq!(idle_result, Err(RunState::Idle));
+        assert!(running_result.is_ok());
+        assert_eq!(
+            *app_state.skip_request.read().unwrap(),
+            Some(SkipTarget::Method)
+        );
+    }
+
+    #[test]
+    fn skip_rejected_without_testing_method() {
+        // GIVEN
+        let app_state = test_app_state(Arc::new(Mutex::new(vec![])));
+        app_state.move_run_state(RunState::Preparing).unwrap();
+        app_state.move_run_state(RunState::Running).unwrap();
+        update_method_response(
+            &app_state,
+            2,
+            &CpuTestMethod::YCruncher,
+            |method_response| method_response.state = CpuTestMethodStatus::Success,
+        );
+
+        // WHEN
+        let result = skip(&app_state, SkipTarget::Method);
+
+        // THEN
+        assert_eq!(result, Err(RunState::Running));
+        assert_eq!(*app_state.skip_request.read().unwrap(), None);
+    }
+
+    #[test]
+    fn initialize_all_core_response_splits_duration() {
+        // GIVEN
+        let all_core_status = Arc::new(RwLock::new(None));
+        let config = CpuTestConfig {
+            duration_per_core: "1m".to_string(),
+            cores_to_test: vec![0, 1, 2, 3],
+            test_methods: vec![CpuTestMethod::Prime95, CpuTestMethod::StressKernel],
+            prime95_config: Prime95Config::default(),
+            ycruncher_config: YCruncherConfig::default(),
+            offset_per_core: HashMap::new(),
+            offset_model: OffsetModel::CurveOptimizer,
+            thermal_config: ThermalConfig::default(),
+            all_core_config: AllCoreConfig {
+                enabled: true,
+                duration: "10m".to_string(),
+            },
+            load_sweep_config: LoadSweepConfig::default(),
+        };
+
+        // WHEN
+        let result = initialize_all_core_response(&all_core_status, &config);
+
+        // THEN
+        assert!(result.is_ok());
+        let all_core_status = all_core_status.read().unwrap().clone().unwrap();
+        assert_eq!(all_core_status.name, "All cores");
+        assert_eq!(all_core_status.core_ids, vec![0, 1, 2, 3]);
+        assert_eq!(
+            all_core_status.method_response[&CpuTestMethod::StressKernel].total_secs,
+            300
+        );
+        assert_eq!(all_core_status.passed(), None);
+    }
+
+    #[test]
+    fn new_all_core_status_without_methods() {
+        // WHEN
+        let result = new_all_core_status(
+            "All cores".to_string(),
+            &[0, 1],
+            &[],
+            Duration::from_secs(600),
+        );
+
+        // THEN
+        assert!(result.method_response.is_empty());
+        assert_eq!(result.passed(), None);
+    }
 }
@@ -0,0 +1,94 @@
+use serde::{Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+
+/// Error returned by the commands, the UI gets a code to match on and a message to show
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandError {
+    /// The config, a profile or a value set in the UI is invalid
+    Config(String),
+    /// The selected cores can not be tested
+    InvalidCores(String),
+    /// A test program or the ryzen_smu driver can not be used
+    BackendUnavailable(String),
+    /// A test is already running
+    AlreadyRunning,
+    /// No test is running that could be stopped
+    NotRunning,
+    /// Reading or writing a file failed
+    Io(String),
+}
+
+#[derive(Serialize)]
+struct SerializedError<'a> {
+    code: &'a str,
+    message: String,
+}
+
+impl CommandError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            CommandError::Config(_) => "config_error",
+            CommandError::InvalidCores(_) => "invalid_cores",
+            CommandError::BackendUnavailable(_) => "backend_unavailable",
+            CommandError::AlreadyRunning => "already_running",
+            CommandError::NotRunning => "not_running",
+            CommandError::Io(_) => "io_error",
+        }
+    }
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandError::Config(message)
+            | CommandError::InvalidCores(message)
+            | CommandError::BackendUnavailable(message)
+            | CommandError::Io(message) => f.write_str(message),
+            CommandError::AlreadyRunning => f.write_str("A test is already running"),
+            CommandError::NotRunning => f.write_str("No test is running"),
+        }
+    }
+}
+
+impl Serialize for CommandError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerializedError {
+            code: self.code(),
+            message: self.to_string(),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialize_with_code_and_message() {
+        // GIVEN
+        let error = CommandError::InvalidCores("No cores selected".to_string());
+
+        // WHEN
+        let result = serde_json::to_value(&error).unwrap();
+
+        // THEN
+        assert_eq!(
+            result,
+            serde_json::json!({"code": "invalid_cores", "message": "No cores selected"})
+        );
+    }
+
+    #[test]
+    fn serialize_already_running() {
+        // GIVEN
+        let error = CommandError::AlreadyRunning;
+
+        // WHEN
+        let result = serde_json::to_value(&error).unwrap();
+
+        // THEN
+        assert_eq!(result["code"], "already_running");
+        assert_eq!(result["message"], "A test is already running");
+    }
+}
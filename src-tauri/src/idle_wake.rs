@@ -0,0 +1,181 @@
+use crate::builtin::{self, BuiltinFailure, MethodControl, XorShift};
+use crate::process;
+use serde::Serialize;
+use std::hint::black_box;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Idle periods between the wakes, long ones let the core drop into deep C-states
+const MIN_IDLE: Duration = Duration::from_micros(200);
+const MAX_IDLE: Duration = Duration::from_millis(20);
+/// Rounds of the verified computation after each wake, only a few microseconds of load
+const ROUNDS: u32 = 200;
+/// Result of `compute(ROUNDS)` on a stable CPU
+const GOLDEN_CHECKSUM: u64 = 0x0908_1CF7_2641_ADD1;
+/// How often the wake latency is reported while the method runs
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long the core took to run again after it should have woken up
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct WakeLatency {
+    /// The number of verified wakes
+    pub wakes: u64,
+    pub average_micros: u64,
+    pub max_micros: u64,
+    #[serde(skip)]
+    total_micros: u64,
+}
+
+impl WakeLatency {
+    fn record(&mut self, latency: Duration) {
+        let micros = latency.as_micros() as u64;
+
+        self.wakes += 1;
+        self.total_micros += micros;
+        self.average_micros = self.total_micros / self.wakes;
+        self.max_micros = self.max_micros.max(micros);
+    }
+}
+
+/// Lets the core idle and wakes it up again until stopped, every other time by a timer or by a thread on another core
+/// Each wake runs a small computation that is compared with the golden checksum
+pub fn run(
+    physical_core_id: usize,
+    waker_core_id: Option<usize>,
+    control: &dyn MethodControl,
+    on_latency: impl Fn(&WakeLatency),
+) -> Result<(), BuiltinFailure> {
+    let (idle_sender, idle_receiver) = mpsc::channel::<Duration>();
+    let (wake_sender, wake_receiver) = mpsc::channel::<Instant>();
+
+    // Waking the core from another core goes through an inter-processor interrupt
+    // The thread ends once the idle sender is dropped
+    thread::Builder::new()
+        .name(format!("idle_wake_thread_{}", physical_core_id))
+        .spawn(move || {
+            if let Some(waker_core_id) = waker_core_id {
+                if let Err(error) = process::pin_current_thread(waker_core_id) {
+                    println!("Failed to pin the waker thread: {}", error);
+                }
+            }
+
+            for idle in idle_receiver {
+                thread::sleep(idle);
+                if wake_sender.send(Instant::now()).is_err() {
+                    break;
+                }
+            }
+        })
+        .map_err(|e| BuiltinFailure::Error(format!("Failed to start the waker thread: {}", e)))?;
+
+    let mut random = XorShift::new(physical_core_id as u64 + 1);
+    let mut wake_latency = WakeLatency::default();
+    let mut last_report = Instant::now();
+
+    while !control.should_stop() {
+        builtin::wait_while_paused(control);
+
+        let idle = random.next_duration(MIN_IDLE, MAX_IDLE);
+        let latency = if wake_latency.wakes % 2 == 0 {
+            let start = Instant::now();
+            thread::sleep(idle);
+            start.elapsed().saturating_sub(idle)
+        } else {
+            let woken_at = idle_sender
+                .send(idle)
+                .ok()
+                .and_then(|_| wake_receiver.recv().ok())
+                .ok_or_else(|| BuiltinFailure::Error("The waker thread stopped".to_string()))?;
+            woken_at.elapsed()
+        };
+
+        let checksum = compute(black_box(ROUNDS));
+        if checksum != GOLDEN_CHECKSUM {
+            return Err(BuiltinFailure::Verification(format!(
+                "Checksum {:#x} instead of {:#x} after {} wakes",
+                checksum, GOLDEN_CHECKSUM, wake_latency.wakes
+            )));
+        }
+        wake_latency.record(latency);
+
+        if last_report.elapsed() >= REPORT_INTERVAL {
+            on_latency(&wake_latency);
+            last_report = Instant::now();
+        }
+    }
+
+    on_latency(&wake_latency);
+    println!(
+        "Idle wake test on core {} finished after {} wakes",
+        physical_core_id, wake_latency.wakes
+    );
+    Ok(())
+}
+
+/// Integer and floating point operations right after the wake, while the core ramps up again
+fn compute(rounds: u32) -> u64 {
+    let mut random = XorShift::new(0xD1B5_4A32_D192_ED03);
+    let mut integer: u64 = 0;
+    let mut float: f64 = 0.5;
+
+    for _ in 0..rounds {
+        let value = random.next_u64();
+        integer = (integer ^ value)
+            .wrapping_mul(0xBF58_476D_1CE4_E5B9)
+            .rotate_right(31);
+        float = (float + (value >> 40) as f64).sqrt() / 1.5;
+    }
+
+    integer ^ float.to_bits()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtin::tests::CountingControl;
+    use std::cell::{Cell, RefCell};
+
+    #[test]
+    fn compute_matches_golden_checksum() {
+        // WHEN
+        let result = compute(black_box(ROUNDS));
+
+        // THEN
+        assert_eq!(result, GOLDEN_CHECKSUM);
+    }
+
+    #[test]
+    fn record_wake_latency() {
+        // GIVEN
+        let mut wake_latency = WakeLatency::default();
+
+        // WHEN
+        wake_latency.record(Duration::from_micros(50));
+        wake_latency.record(Duration::from_micros(250));
+        wake_latency.record(Duration::from_micros(90));
+
+        // THEN
+        assert_eq!(wake_latency.wakes, 3);
+        assert_eq!(wake_latency.average_micros, 130);
+        assert_eq!(wake_latency.max_micros, 250);
+    }
+
+    #[test]
+    fn run_until_stopped() {
+        // GIVEN
+        let control = CountingControl {
+            checks_left: Cell::new(4),
+        };
+        let reported = RefCell::new(None);
+
+        // WHEN
+        let result = run(0, None, &control, |wake_latency| {
+            *reported.borrow_mut() = Some(wake_latency.clone())
+        });
+
+        // THEN
+        assert_eq!(result, Ok(()));
+        assert_eq!(reported.into_inner().unwrap().wakes, 4);
+    }
+}
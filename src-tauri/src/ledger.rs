@@ -0,0 +1,321 @@
+use crate::config::{self, CONFIG_PATH};
+use crate::cpu_test::{CpuTestMethod, CpuTestMethodStatus, CpuTestStatus};
+use crate::offset_model::OffsetModel;
+use chrono::Utc;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+lazy_static! {
+    pub static ref LEDGER_PATH: PathBuf = CONFIG_PATH.parent().unwrap().join("ledger.json");
+}
+
+/// The results of all runs of a core at one offset
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OffsetRecord {
+    pub passed: u32,
+    pub failed: u32,
+    pub total_secs: u64,
+    pub methods: Vec<CpuTestMethod>,
+    /// RFC 3339 timestamp of the last run
+    pub last_tested: String,
+}
+
+/// The records of each core keyed by offset
+pub type CoreRecords = HashMap<usize, BTreeMap<i32, OffsetRecord>>;
+
+/// Stability history of all cores, kept per offset model as the offsets have different units
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Ledger {
+    pub models: HashMap<OffsetModel, CoreRecords>,
+}
+
+/// The records of a core with the recommended offset, as shown in the UI
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CoreLedger {
+    pub core_id: usize,
+    pub records: BTreeMap<i32, OffsetRecord>,
+    pub recommended_offset: Option<i32>,
+}
+
+/// Loads the ledger, an empty ledger if none was recorded yet
+pub fn load(path: &Path) -> Result<Ledger, String> {
+    if !path.exists() {
+        return Ok(Ledger::default());
+    }
+
+    let ledger_str = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&ledger_str)
+        .map_err(|e| format!("Invalid ledger {}: {}", path.display(), e))
+}
+
+pub fn save(ledger: &Ledger, path: &Path) -> Result<(), String> {
+    let ledger_str = serde_json::to_string(ledger).unwrap();
+    config::write_atomic(path, ledger_str.as_bytes())
+}
+
+/// Adds the result of the tested core to the ledger file
+pub fn record_core(
+    cpu_test_status: &CpuTestStatus,
+    offset: i32,
+    offset_model: OffsetModel,
+) -> Result<(), String> {
+    let mut ledger = load(&LEDGER_PATH)?;
+    add_result(&mut ledger, cpu_test_status, offset, offset_model);
+    save(&ledger, &LEDGER_PATH)
+}
+
+/// Adds the result of the core to the record of the offset and model it was tested with
+/// Cores without a definite result, e.g. stopped by the user or errored, are skipped
+fn add_result(
+    ledger: &mut Ledger,
+    cpu_test_status: &CpuTestStatus,
+    offset: i32,
+    offset_model: OffsetModel,
+) {
+    let Some(passed) = cpu_test_status.passed() else {
+        return;
+    };
+
+    let record = ledger
+        .models
+        .entry(offset_model)
+        .or_default()
+        .entry(cpu_test_status.core_id)
+        .or_default()
+        .entry(offset)
+        .or_default();
+
+    if passed {
+        record.passed += 1;
+    } else {
+        record.failed += 1;
+    }
+
+    for method_response in cpu_test_status.method_response.values() {
+        record.total_secs += method_response.current_secs;
+
+        let was_tested = matches!(
+            method_response.state,
+            CpuTestMethodStatus::Success | CpuTestMethodStatus::Failed
+        );
+        if was_tested && !record.methods.contains(&method_response.method) {
+            record.methods.push(method_response.method);
+        }
+    }
+    record.last_tested = Utc::now().to_rfc3339();
+}
+
+/// Returns the records of each core for the offset model with its recommended offset
+pub fn summarize(ledger: &Ledger, offset_model: OffsetModel) -> Vec<CoreLedger> {
+    let Some(core_records) = ledger.models.get(&offset_model) else {
+        return vec![];
+    };
+
+    let mut core_ledgers: Vec<CoreLedger> = core_records
+        .iter()
+        .map(|(core_id, records)| CoreLedger {
+            core_id: *core_id,
+            records: records.clone(),
+            recommended_offset: recommended_offset(records, offset_model),
+        })
+        .collect();
+    core_ledgers.sort_by_key(|core_ledger| core_ledger.core_id);
+
+    core_ledgers
+}
+
+/// The most aggressive offset that never failed and is more stable than any failed offset
+/// The stable direction depends on the model, e.g. towards zero for the Curve Optimizer
+fn recommended_offset(
+    records: &BTreeMap<i32, OffsetRecord>,
+    offset_model: OffsetModel,
+) -> Option<i32> {
+    // Higher values are more stable
+    let stability = |offset: i32| offset * offset_model.stabilizing_step().signum();
+    let most_stable_failed = records
+        .iter()
+        .filter(|(_, record)| record.failed > 0)
+        .map(|(offset, _)| stability(*offset))
+        .max();
+
+    records
+        .iter()
+        .filter(|(offset, record)| {
+            record.passed > 0
+                && record.failed == 0
+                && !matches!(most_stable_failed, Some(failed) if stability(**offset) <= failed)
+        })
+        .map(|(offset, _)| *offset)
+        .min_by_key(|offset| stability(*offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu_test::TestMethodResponse;
+
+    fn test_status(
+        core_id: usize,
+        states: &[(CpuTestMethod, CpuTestMethodStatus)],
+    ) -> CpuTestStatus {
+        CpuTestStatus {
+            core_id,
+            max_clock: 5000,
+            verification_failed: false,
+            method_response: states
+                .iter()
+                .map(|(method, state)| {
+                    let response = TestMethodResponse {
+                        method: *method,
+                        state: *state,
+                        current_secs: 60,
+                        total_secs: 60,
+                        error: None,
+                        prime95_progress: None,
+                        wake_latency: None,
+                    };
+                    (*method, response)
+                })
+                .collect(),
+        }
+    }
+
+    fn record(passed: u32, failed: u32) -> OffsetRecord {
+        OffsetRecord {
+            passed,
+            failed,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn add_result_counts_pass_and_fail() {
+        // GIVEN
+        let mut ledger = Ledger::default();
+        let passed_core = test_status(
+            0,
+            &[
+                (CpuTestMethod::Prime95, CpuTestMethodStatus::Success),
+                (CpuTestMethod::YCruncher, CpuTestMethodStatus::Success),
+            ],
+        );
+        let failed_core = test_status(
+            1,
+            &[
+                (CpuTestMethod::Prime95, CpuTestMethodStatus::Failed),
+                (CpuTestMethod::YCruncher, CpuTestMethodStatus::Idle),
+            ],
+        );
+
+        // WHEN
+        for _ in 0..2 {
+            add_result(&mut ledger, &passed_core, -20, OffsetModel::CurveOptimizer);
+            add_result(&mut ledger, &failed_core, -22, OffsetModel::CurveOptimizer);
+        }
+
+        // THEN
+        let records = &ledger.models[&OffsetModel::CurveOptimizer];
+        let core_0 = &records[&0][&-20];
+        assert_eq!(
+            (core_0.passed, core_0.failed, core_0.total_secs),
+            (2, 0, 240)
+        );
+        assert_eq!(core_0.methods.len(), 2);
+        let core_1 = &records[&1][&-22];
+        assert_eq!((core_1.passed, core_1.failed), (0, 2));
+        assert_eq!(core_1.methods, vec![CpuTestMethod::Prime95]);
+    }
+
+    #[test]
+    fn add_result_skips_unfinished_core() {
+        // GIVEN
+        let mut ledger = Ledger::default();
+        let unfinished_core = test_status(
+            2,
+            &[
+                (CpuTestMethod::Prime95, CpuTestMethodStatus::Success),
+                (CpuTestMethod::YCruncher, CpuTestMethodStatus::Error),
+            ],
+        );
+
+        // WHEN
+        add_result(
+            &mut ledger,
+            &unfinished_core,
+            0,
+            OffsetModel::CurveOptimizer,
+        );
+
+        // THEN
+        assert!(ledger.models.is_empty());
+    }
+
+    #[test]
+    fn recommended_offset_above_failed_offset() {
+        // GIVEN
+        let records = BTreeMap::from([
+            (-25, record(1, 0)),
+            (-22, record(0, 1)),
+            (-20, record(3, 0)),
+            (-15, record(1, 0)),
+        ]);
+
+        // WHEN
+        let result = recommended_offset(&records, OffsetModel::CurveOptimizer);
+
+        // THEN
+        assert_eq!(result, Some(-20));
+    }
+
+    #[test]
+    fn recommended_offset_core_ratio_below_failed_ratio() {
+        // GIVEN
+        let records = BTreeMap::from([
+            (1, record(2, 0)),
+            (2, record(3, 0)),
+            (3, record(0, 1)),
+            (4, record(1, 0)),
+        ]);
+
+        // WHEN
+        let result = recommended_offset(&records, OffsetModel::CoreRatio);
+
+        // THEN
+        assert_eq!(result, Some(2));
+    }
+
+    #[test]
+    fn recommended_offset_without_passed_offset() {
+        // GIVEN
+        let records = BTreeMap::from([(-10, record(0, 2))]);
+
+        // WHEN
+        let result = recommended_offset(&records, OffsetModel::CurveOptimizer);
+
+        // THEN
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn save_and_load() {
+        // GIVEN
+        let path = std::env::temp_dir().join("pbo-assistant-ledger-test.json");
+        let ledger = Ledger {
+            models: HashMap::from([(
+                OffsetModel::VoltageOffset,
+                HashMap::from([(3, BTreeMap::from([(-50, record(4, 1))]))]),
+            )]),
+        };
+
+        // WHEN
+        save(&ledger, &path).unwrap();
+        let result = load(&path);
+
+        // THEN
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result.unwrap(), ledger);
+    }
+}
@@ -0,0 +1,415 @@
+use crate::all_core::AllCoreStatus;
+use crate::cpu_test::CpuTestMethodStatus;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The test mode that loads growing sets of cores per L3 group instead of one core after another
+/// An L3 group are the cores sharing an L3 cache, a CCD or a CCX on Zen 2
+/// Boost clocks and voltages depend on the number of active cores, so some offsets only fail with a few cores loaded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoadSweepConfig {
+    pub enabled: bool,
+    /// The duration of each set of cores, split between the methods like the duration per core
+    pub duration_per_step: String,
+}
+
+impl Default for LoadSweepConfig {
+    fn default() -> Self {
+        LoadSweepConfig {
+            enabled: false,
+            duration_per_step: "5m".to_string(),
+        }
+    }
+}
+
+impl LoadSweepConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        parse_duration::parse(&self.duration_per_step).map_err(|e| {
+            format!(
+                "Invalid load sweep duration {}: {}. Examples: 1h, 30m, 10s",
+                self.duration_per_step, e
+            )
+        })?;
+
+        Ok(())
+    }
+}
+
+/// A set of cores of an L3 group that failed while loaded together
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SweepFinding {
+    pub l3_group: usize,
+    pub failed_set: Vec<usize>,
+    /// The core the failure was bisected to
+    /// All cores of the set if the failure could not be narrowed down, e.g. it only occurs with all of them loaded
+    pub offending_core_ids: Vec<usize>,
+}
+
+/// The result of the load sweep
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LoadSweepStatus {
+    /// The tested sets of cores in order, including the ones of the bisection
+    pub steps: Vec<AllCoreStatus>,
+    pub findings: Vec<SweepFinding>,
+}
+
+/// The number of loaded cores per step, 1, 2, 4, ... and finally all cores of the L3 group
+pub fn step_sizes(core_count: usize) -> Vec<usize> {
+    let mut sizes: Vec<usize> = std::iter::successors(Some(1), |size| Some(size * 2))
+        .take_while(|size| *size < core_count)
+        .collect();
+    if core_count > 0 {
+        sizes.push(core_count);
+    }
+    sizes
+}
+
+/// The sets of the given size that load every core once, e.g. [0, 1], [2, 3] and [4, 0] for 5 cores
+pub fn sets_of_size(core_ids: &[usize], size: usize) -> Vec<Vec<usize>> {
+    if core_ids.is_empty() || size == 0 {
+        return vec![];
+    }
+
+    (0..core_ids.len().div_ceil(size))
+        .map(|set_index| {
+            (0..size)
+                .map(|index| core_ids[(set_index * size + index) % core_ids.len()])
+                .collect()
+        })
+        .collect()
+}
+
+/// The outcome of loading a set of cores together
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SetOutcome {
+    Passed,
+    /// A core failed or a test program did not run as expected
+    Failed,
+    /// Skipped by the user or aborted above the max temperature, says nothing about the set
+    Skipped,
+    /// Stopped by the user, ends the sweep
+    Stopped,
+}
+
+impl SetOutcome {
+    /// Returns the outcome of the tested set, stopped overrides the state of the methods
+    pub fn of(status: &AllCoreStatus, stopped: bool) -> SetOutcome {
+        if stopped {
+            return SetOutcome::Stopped;
+        }
+
+        let any_error = status
+            .method_response
+            .values()
+            .any(|method_response| method_response.state == CpuTestMethodStatus::Error);
+        match status.passed() {
+            Some(false) => SetOutcome::Failed,
+            _ if any_error => SetOutcome::Failed,
+            Some(true) => SetOutcome::Passed,
+            None => SetOutcome::Skipped,
+        }
+    }
+}
+
+/// Loads growing sets of the cores of the L3 group until one fails and bisects the failed set
+/// Each core is part of a set of every size, a set is tested only once, also by the bisection
+/// A skipped set is left out, only a stop ends the sweep
+/// Returns None if no set failed
+pub fn sweep_l3_group(
+    l3_group: usize,
+    core_ids: &[usize],
+    test: &mut dyn FnMut(&[usize]) -> SetOutcome,
+) -> Option<SweepFinding> {
+    let mut outcomes: HashMap<Vec<usize>, SetOutcome> = HashMap::new();
+    let mut test_once = |set: &[usize]| {
+        let mut key = set.to_vec();
+        key.sort();
+        if let Some(outcome) = outcomes.get(&key) {
+            return *outcome;
+        }
+
+        // Only a definite result is kept, a skipped set is tested again if the bisection needs it
+        let outcome = test(set);
+        if matches!(outcome, SetOutcome::Passed | SetOutcome::Failed) {
+            outcomes.insert(key, outcome);
+        }
+        outcome
+    };
+
+    for size in step_sizes(core_ids.len()) {
+        for set in sets_of_size(core_ids, size) {
+            match test_once(&set) {
+                SetOutcome::Passed | SetOutcome::Skipped => continue,
+                SetOutcome::Stopped => return None,
+                SetOutcome::Failed => {}
+            }
+
+            // If the bisection is stopped, the failure stays attributed to the whole set
+            let offending_core_ids = bisect(&set, &mut test_once).unwrap_or_else(|| set.clone());
+            return Some(SweepFinding {
+                l3_group,
+                failed_set: set,
+                offending_core_ids,
+            });
+        }
+    }
+
+    None
+}
+
+/// Narrows the failure of the set down by testing each half alone
+/// Returns the set itself if neither half fails alone, None if the sweep was stopped
+pub fn bisect(set: &[usize], test: &mut dyn FnMut(&[usize]) -> SetOutcome) -> Option<Vec<usize>> {
+    if set.len() <= 1 {
+        return Some(set.to_vec());
+    }
+
+    let (first_half, second_half) = set.split_at(set.len() / 2);
+    for half in [first_half, second_half] {
+        match test(half) {
+            SetOutcome::Failed => return bisect(half, test),
+            SetOutcome::Stopped => return None,
+            SetOutcome::Passed | SetOutcome::Skipped => {}
+        }
+    }
+
+    Some(set.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu_test::{CpuTestMethod, TestMethodResponse};
+
+    /// Fails every set that contains all of the unstable cores and records the tested sets
+    fn failing_with<'a>(
+        unstable_core_ids: &'a [usize],
+        tested_sets: &'a mut Vec<Vec<usize>>,
+    ) -> impl FnMut(&[usize]) -> SetOutcome + 'a {
+        move |set: &[usize]| {
+            tested_sets.push(set.to_vec());
+            if unstable_core_ids
+                .iter()
+                .all(|core_id| set.contains(core_id))
+            {
+                SetOutcome::Failed
+            } else {
+                SetOutcome::Passed
+            }
+        }
+    }
+
+    #[test]
+    fn step_sizes_up_to_all_cores() {
+        // WHEN
+        let result = [0, 1, 6, 8].map(step_sizes);
+
+        // THEN
+        assert_eq!(
+            result,
+            [vec![], vec![1], vec![1, 2, 4, 6], vec![1, 2, 4, 8]]
+        );
+    }
+
+    #[test]
+    fn sets_of_size_rotate_all_cores() {
+        // WHEN
+        let result = [1, 2, 4].map(|size| sets_of_size(&[0, 1, 2, 3, 4], size));
+
+        // THEN
+        assert_eq!(
+            result,
+            [
+                vec![vec![0], vec![1], vec![2], vec![3], vec![4]],
+                vec![vec![0, 1], vec![2, 3], vec![4, 0]],
+                vec![vec![0, 1, 2, 3], vec![4, 0, 1, 2]],
+            ]
+        );
+    }
+
+    #[test]
+    fn sweep_l3_group_finds_single_core() {
+        // GIVEN
+        let mut tested_sets = vec![];
+        let mut test = failing_with(&[6], &mut tested_sets);
+
+        // WHEN
+        let result = sweep_l3_group(1, &[4, 5, 6, 7], &mut test);
+
+        // THEN
+        assert_eq!(
+            result,
+            Some(SweepFinding {
+                l3_group: 1,
+                failed_set: vec![6],
+                offending_core_ids: vec![6],
+            })
+        );
+        drop(test);
+        assert_eq!(tested_sets, vec![vec![4], vec![5], vec![6]]);
+    }
+
+    #[test]
+    fn sweep_l3_group_bisects_without_retesting() {
+        // GIVEN
+        let mut tested_sets = vec![];
+        let mut test = failing_with(&[5, 6], &mut tested_sets);
+
+        // WHEN
+        let result = sweep_l3_group(1, &[4, 5, 6, 7], &mut test);
+
+        // THEN
+        assert_eq!(
+            result,
+            Some(SweepFinding {
+                l3_group: 1,
+                failed_set: vec![4, 5, 6, 7],
+                offending_core_ids: vec![4, 5, 6, 7],
+            })
+        );
+        drop(test);
+        assert_eq!(
+            tested_sets,
+            vec![
+                vec![4],
+                vec![5],
+                vec![6],
+                vec![7],
+                vec![4, 5],
+                vec![6, 7],
+                vec![4, 5, 6, 7],
+            ]
+        );
+    }
+
+    #[test]
+    fn sweep_l3_group_fails_only_together() {
+        // GIVEN
+        let mut tested_sets = vec![];
+        let mut test = failing_with(&[0, 2], &mut tested_sets);
+
+        // WHEN
+        let result = sweep_l3_group(0, &[0, 1, 2], &mut test);
+
+        // THEN
+        let finding = result.unwrap();
+        assert_eq!(finding.failed_set, vec![2, 0]);
+        assert_eq!(finding.offending_core_ids, vec![2, 0]);
+    }
+
+    #[test]
+    fn sweep_l3_group_passed() {
+        // GIVEN
+        let mut tested_sets = vec![];
+        let mut test = failing_with(&[9], &mut tested_sets);
+
+        // WHEN
+        let result = sweep_l3_group(0, &[0, 1, 2], &mut test);
+
+        // THEN
+        assert_eq!(result, None);
+        drop(test);
+        assert_eq!(tested_sets.len(), 6);
+    }
+
+    #[test]
+    fn sweep_l3_group_stopped_during_bisection() {
+        // GIVEN
+        // All sets pass until [4, 0, 1, 2], its half [1, 2] is the first set not tested yet
+        let mut results = [
+            vec![SetOutcome::Passed; 9],
+            vec![SetOutcome::Failed, SetOutcome::Stopped],
+        ]
+        .concat()
+        .into_iter();
+        let mut test = |_: &[usize]| results.next().unwrap();
+
+        // WHEN
+        let result = sweep_l3_group(0, &[0, 1, 2, 3, 4], &mut test);
+
+        // THEN
+        let finding = result.unwrap();
+        assert_eq!(finding.failed_set, vec![4, 0, 1, 2]);
+        assert_eq!(finding.offending_core_ids, vec![4, 0, 1, 2]);
+    }
+
+    #[test]
+    fn sweep_l3_group_continues_after_skipped_set() {
+        // GIVEN
+        let mut tested_sets = vec![];
+        let mut failing = failing_with(&[2], &mut tested_sets);
+        let mut test = |set: &[usize]| {
+            if set == [1] {
+                SetOutcome::Skipped
+            } else {
+                failing(set)
+            }
+        };
+
+        // WHEN
+        let result = sweep_l3_group(0, &[0, 1, 2, 3], &mut test);
+
+        // THEN
+        assert_eq!(result.unwrap().offending_core_ids, vec![2]);
+    }
+
+    #[test]
+    fn sweep_l3_group_stopped() {
+        // GIVEN
+        let mut results = [SetOutcome::Passed, SetOutcome::Stopped].into_iter();
+        let mut test = |_: &[usize]| results.next().unwrap();
+
+        // WHEN
+        let result = sweep_l3_group(0, &[0, 1, 2, 3], &mut test);
+
+        // THEN
+        assert_eq!(result, None);
+        assert_eq!(results.next(), None);
+    }
+
+    #[test]
+    fn set_outcome_of_status() {
+        // GIVEN
+        let status_with = |state: CpuTestMethodStatus| AllCoreStatus {
+            name: "L3 group 0: cores 0, 1".to_string(),
+            core_ids: vec![0, 1],
+            method_response: HashMap::from([(
+                CpuTestMethod::StressKernel,
+                TestMethodResponse {
+                    method: CpuTestMethod::StressKernel,
+                    state,
+                    current_secs: 0,
+                    total_secs: 60,
+                    error: None,
+                    prime95_progress: None,
+                    wake_latency: None,
+                },
+            )]),
+            failed_core_ids: vec![],
+        };
+
+        // WHEN
+        let result = [
+            SetOutcome::of(&status_with(CpuTestMethodStatus::Success), false),
+            SetOutcome::of(&status_with(CpuTestMethodStatus::Error), false),
+            SetOutcome::of(&status_with(CpuTestMethodStatus::ThermalAbort), false),
+            SetOutcome::of(&status_with(CpuTestMethodStatus::Success), true),
+        ];
+
+        // THEN
+        assert_eq!(
+            result,
+            [
+                SetOutcome::Passed,
+                SetOutcome::Failed,
+                SetOutcome::Skipped,
+                SetOutcome::Stopped,
+            ]
+        );
+    }
+}
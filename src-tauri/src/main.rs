@@ -3,143 +3,604 @@
 
 use std::collections::HashMap;
 use std::ops::Deref;
-use std::str::FromStr;
 use std::sync::{Arc, RwLock};
 use std::thread;
 
 use cpu_test::AppState;
 use strum::IntoEnumIterator;
+use tauri::{Emitter, Manager};
 
-use crate::cpu_test::CpuTestStatus;
+use crate::all_core::AllCoreStatus;
+use crate::binary::BinaryInfo;
+use crate::config::AppConfig;
+use crate::cpu_test::{CpuTestMethod, CpuTestStatus, RunState, SkipTarget};
+use crate::error::CommandError;
+use crate::offset_model::{OffsetModel, OffsetModelSettings};
 
+mod all_core;
+mod binary;
+mod boost_transition;
+mod builtin;
 mod config;
 mod cpu_info;
 mod cpu_test;
+mod error;
+mod idle_wake;
+mod ledger;
+mod load_sweep;
 mod mprime;
+mod offset_model;
 mod process;
+mod profile;
+mod ryzen_smu;
+mod stress_kernel;
+mod suggestion;
+mod thermal;
 mod ycruncher;
 
 fn main() {
+    let config_store = config::ConfigStore::new(config::CONFIG_PATH.clone());
+
+    // Extracting and probing the binaries takes a while, do not delay the window
+    let config_store_for_check = config_store.clone();
+    thread::spawn(move || check_binaries(&config_store_for_check));
+
     tauri::Builder::default()
-        .manage(AppState {
-            test_status: Arc::new(RwLock::new(HashMap::new())),
-            terminated_by_user: Arc::new(RwLock::new(false)),
-            config_write_lock: Arc::new(RwLock::new(false)),
+        .setup(|app| {
+            // Push the status changes of a test run to the UI
+            let app_handle = app.handle().clone();
+            let events = cpu_test::TestEventSink::new(move |event| {
+                if let Err(e) = app_handle.emit(event.name(), &event) {
+                    println!("Failed to emit {}: {}", event.name(), e);
+                }
+            });
+            app.manage(AppState {
+                test_status: Arc::new(RwLock::new(HashMap::new())),
+                terminated_by_user: Arc::new(RwLock::new(false)),
+                config_store: config_store.clone(),
+                events,
+                run_state: Arc::new(RwLock::new(RunState::Idle)),
+                skip_request: Arc::new(RwLock::new(None)),
+                all_core_status: Arc::new(RwLock::new(None)),
+                tested_offsets: Arc::new(RwLock::new(None)),
+            });
+
+            // Let the UI reload the config when the file was edited outside the app
+            let app_handle = app.handle().clone();
+            config::watch(
+                config_store,
+                cpu_info::get_physical_cores(),
+                move |result| {
+                    let emitted = match result {
+                        Ok(config) => app_handle.emit("config-changed", config),
+                        Err(error) => app_handle.emit("config-error", error),
+                    };
+                    if let Err(e) = emitted {
+                        println!("Failed to emit config change: {}", e);
+                    }
+                },
+            );
+            Ok(())
         })
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .invoke_handler(tauri::generate_handler![
             get_config,
             get_test_methods,
+            get_ycruncher_tests,
             start_test,
             stop_test,
             get_test_status,
+            get_all_core_status,
+            get_run_state,
+            pause_test,
+            resume_test,
+            skip_test,
             get_physical_cores,
+            get_binary_info,
+            cleanup_binaries,
             set_offset,
+            get_smu_status,
+            set_apply_offsets_via_smu,
+            get_smu_offsets,
+            save_profile,
+            switch_profile,
+            delete_profile,
+            import_profile,
+            export_profile,
+            get_ledger,
+            get_offset_suggestions,
+            apply_offset_suggestions,
+            get_offset_model,
+            set_offset_model,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            // Write debounced config changes before exiting
+            if let tauri::RunEvent::Exit = event {
+                if let Err(error) = app_handle.state::<AppState>().config_store.flush() {
+                    println!("Failed to save the config: {}", error);
+                }
+            }
+        });
 }
 
 #[tauri::command]
-async fn get_config(app_state: tauri::State<'_, AppState>) -> Result<String, String> {
-    let config = config::load_config(&app_state.config_write_lock);
-    Ok(serde_json::to_string(&config).unwrap())
+async fn get_config(
+    app_state: tauri::State<'_, AppState>,
+) -> Result<config::LoadedConfig, CommandError> {
+    Ok(config::LoadedConfig {
+        config: app_state.config_store.load(),
+        warning: app_state.config_store.take_load_warning(),
+    })
+}
+
+/// Reports which stress test binary each backend will use, the result is cached for `get_binary_info`
+fn check_binaries(config_store: &config::ConfigStore) {
+    let config = config_store.load();
+    mprime::print_binary_info(&mprime::resolve_binary(&config.prime95));
+    ycruncher::print_binary_info(&ycruncher::resolve_binary(&config.ycruncher));
 }
 
 #[tauri::command]
-async fn get_physical_cores() -> Result<usize, ()> {
-    Ok(cpu_info::get_physical_cores())
+async fn get_binary_info(
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Vec<BinaryInfo>, CommandError> {
+    let config = app_state.config_store.load();
+    Ok(vec![
+        mprime::resolve_binary(&config.prime95),
+        ycruncher::resolve_binary(&config.ycruncher),
+    ])
 }
 
+/// Removes extracted binaries of other embedded versions
 #[tauri::command]
-async fn stop_test(app_state: tauri::State<'_, AppState>) -> Result<(), ()> {
+async fn cleanup_binaries(
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Vec<String>, CommandError> {
+    ensure_not_running(&app_state)?;
+
+    let current_dirs = vec![mprime::process_dir(), ycruncher::version_dir()];
+    binary::remove_stale_dirs(binary::RUNTIME_DIR.as_path(), &current_dirs)
+        .map_err(CommandError::Io)
+}
+
+#[tauri::command]
+async fn get_physical_cores() -> usize {
+    cpu_info::get_physical_cores()
+}
+
+#[tauri::command]
+async fn stop_test(app_state: tauri::State<'_, AppState>) -> Result<(), CommandError> {
     let state = app_state.deref();
+    match state.move_run_state(RunState::Stopping) {
+        Ok(()) => {}
+        // Stopping twice is fine, e.g. after a double click
+        Err(RunState::Stopping) => return Ok(()),
+        Err(_) => return Err(CommandError::NotRunning),
+    }
+
     cpu_test::stop(state.clone());
     Ok(())
 }
 
 #[tauri::command]
-async fn get_test_status(app_state: tauri::State<'_, AppState>) -> Result<String, ()> {
+async fn get_test_status(
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Vec<CpuTestStatus>, CommandError> {
     // Read values from state
     let state = app_state.deref();
     let test_status = state.test_status.read().unwrap();
-    let mut test_status_values: Vec<&CpuTestStatus> = test_status.deref().values().collect();
+    let mut test_status_values: Vec<CpuTestStatus> =
+        test_status.deref().values().cloned().collect();
 
     // Sort by core id
     test_status_values.sort_by(|a, b| a.core_id.cmp(&b.core_id));
 
-    Ok(serde_json::to_string(&test_status_values).unwrap())
+    Ok(test_status_values)
+}
+
+#[tauri::command]
+async fn get_all_core_status(
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Option<AllCoreStatus>, CommandError> {
+    Ok(app_state.all_core_status.read().unwrap().clone())
 }
 
 #[tauri::command]
-async fn get_test_methods() -> Result<String, String> {
-    let cpu_test_methods = cpu_test::CpuTestMethod::iter()
-        .map(|method| method.to_string())
-        .collect::<Vec<String>>();
-    Ok(serde_json::to_string(&cpu_test_methods).unwrap())
+async fn get_test_methods() -> Vec<CpuTestMethod> {
+    CpuTestMethod::iter().collect()
+}
+
+#[tauri::command]
+async fn get_ycruncher_tests() -> Vec<ycruncher::YCruncherTest> {
+    ycruncher::YCruncherTest::iter().collect()
 }
 
 #[tauri::command]
 async fn start_test(
-    test_methods: Vec<String>,
-    duration_per_core: String,
-    cores_to_test: String,
-    app_config: String,
+    config: AppConfig,
     app_state: tauri::State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
+    let app_state = app_state.deref();
+
+    // Only one test can run at a time
+    cpu_test::start(app_state).map_err(|_| CommandError::AlreadyRunning)?;
+
+    let test_config = match prepare_test(&config, app_state) {
+        Ok(test_config) => test_config,
+        Err(error) => {
+            // Stopped while preparing, the stop waits for the run to finish
+            if app_state.move_run_state(RunState::Idle).is_err() {
+                cpu_test::finish(app_state, None);
+            }
+            return Err(error);
+        }
+    };
+
+    // Stopped while preparing, e.g. while the offsets were applied
+    if app_state.move_run_state(RunState::Running).is_err() {
+        cpu_test::finish(app_state, None);
+        return Ok(());
+    }
+
+    let core_status = app_state.clone();
+    thread::spawn(move || {
+        cpu_test::run(core_status, &test_config);
+    });
+
+    Ok(())
+}
+
+/// Validates and saves the config, applies the offsets and resets the test results
+fn prepare_test(
+    config: &AppConfig,
+    app_state: &AppState,
+) -> Result<cpu_test::CpuTestConfig, CommandError> {
     // Save app config
-    let app_config: config::AppConfig = serde_json::from_str(&app_config).unwrap();
-    config::save_config(&app_config, &app_state.config_write_lock);
+    config
+        .validate(cpu_info::get_physical_cores())
+        .map_err(CommandError::Config)?;
+    app_state.config_store.save(config.clone());
 
     // Cleanup and validate cores to test
-    let cores_to_test_parsed =
-        cpu_test::get_cores_to_test(&cores_to_test, cpu_info::get_physical_cores())?;
+    let cores_to_test =
+        cpu_test::get_cores_to_test(&config.cores_to_test, cpu_info::get_physical_cores())
+            .map_err(CommandError::InvalidCores)?;
 
     // If cores to test is empty, return error
-    if cores_to_test_parsed.is_empty() {
-        return Err(format!(
+    if cores_to_test.is_empty() {
+        return Err(CommandError::InvalidCores(format!(
             "No cores selected with selection criteria: {}",
-            cores_to_test
-        )
-        .to_string());
+            config.cores_to_test
+        )));
     }
 
-    let test_methods = test_methods
-        .iter()
-        .map(|method| cpu_test::CpuTestMethod::from_str(method).unwrap())
-        .collect::<Vec<cpu_test::CpuTestMethod>>();
+    // Apply the stored offsets of the cores to test
+    if config.applies_offsets_at_runtime() {
+        let offsets: Vec<(usize, i32)> = cores_to_test
+            .iter()
+            .filter_map(|core_id| {
+                config
+                    .offset_per_core
+                    .get(core_id)
+                    .map(|offset| (*core_id, *offset))
+            })
+            .collect();
+        ryzen_smu::apply_offsets(&offsets).map_err(CommandError::BackendUnavailable)?;
+    }
 
     let test_config = cpu_test::CpuTestConfig {
-        test_methods,
-        duration_per_core: duration_per_core.parse().unwrap(),
-        cores_to_test: cores_to_test_parsed,
+        test_methods: config.active_test_methods.clone(),
+        duration_per_core: config.test_duration_per_core.clone(),
+        cores_to_test,
+        prime95_config: config.prime95.clone(),
+        ycruncher_config: config.ycruncher.clone(),
+        offset_per_core: config.offset_per_core.clone(),
+        offset_model: config.resolved_offset_model(),
+        thermal_config: config.thermal.clone(),
+        all_core_config: config.all_core.clone(),
+        load_sweep_config: config.load_sweep.clone(),
     };
 
-    let app_state = app_state.deref();
+    // Reset skips requested and suggestions of the last run
+    app_state.skip_request.write().unwrap().take();
+    app_state.tested_offsets.write().unwrap().take();
 
-    // Reset terminated by user flag
-    *app_state.terminated_by_user.write().unwrap() = false;
+    cpu_test::initialize_response(&app_state.test_status, &test_config)
+        .map_err(CommandError::Config)?;
+    cpu_test::initialize_all_core_response(&app_state.all_core_status, &test_config)
+        .map_err(CommandError::Config)?;
 
-    cpu_test::initialize_response(&app_state.test_status, &test_config)?;
+    Ok(test_config)
+}
 
-    let core_status = app_state.clone();
-    thread::spawn(move || {
-        cpu_test::run(core_status, &test_config);
-    });
+/// Rejects changes that would interfere with a running test, e.g. changing the offsets
+fn ensure_not_running(app_state: &AppState) -> Result<(), CommandError> {
+    if app_state.run_state().is_active() {
+        return Err(CommandError::AlreadyRunning);
+    }
 
     Ok(())
 }
 
+#[tauri::command]
+async fn pause_test(app_state: tauri::State<'_, AppState>) -> Result<(), CommandError> {
+    cpu_test::pause(&app_state).map_err(|_| CommandError::NotRunning)
+}
+
+#[tauri::command]
+async fn resume_test(app_state: tauri::State<'_, AppState>) -> Result<(), CommandError> {
+    cpu_test::resume(&app_state).map_err(|_| CommandError::NotRunning)
+}
+
+/// Skips the current method or all remaining methods of the current core
+#[tauri::command]
+async fn skip_test(
+    target: SkipTarget,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<(), CommandError> {
+    cpu_test::skip(&app_state, target).map_err(|_| CommandError::NotRunning)
+}
+
+#[tauri::command]
+async fn get_run_state(app_state: tauri::State<'_, AppState>) -> Result<RunState, CommandError> {
+    Ok(app_state.run_state())
+}
+
+/// Returns the stability ledger of all cores with their recommended offsets
+#[tauri::command]
+async fn get_ledger(
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Vec<ledger::CoreLedger>, CommandError> {
+    let config = app_state.config_store.load();
+    let ledger = ledger::load(&ledger::LEDGER_PATH).map_err(CommandError::Io)?;
+    Ok(ledger::summarize(&ledger, config.resolved_offset_model()))
+}
+
+/// Suggests new offsets based on the results of the last run and the offsets it tested
+/// Empty once the suggestions were applied
+#[tauri::command]
+async fn get_offset_suggestions(
+    include_passed: bool,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Vec<suggestion::OffsetSuggestion>, CommandError> {
+    let config = app_state.config_store.load();
+    let Some(tested_offsets) = app_state.tested_offsets.read().unwrap().clone() else {
+        return Ok(vec![]);
+    };
+
+    let test_status = app_state.test_status.read().unwrap();
+    Ok(suggestion::suggest_offsets(
+        &test_status,
+        &tested_offsets,
+        config.resolved_offset_model(),
+        include_passed,
+    ))
+}
+
+/// Sets the suggested offsets of all cores at once, returns the updated config
+#[tauri::command]
+fn apply_offset_suggestions(
+    include_passed: bool,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<AppConfig, CommandError> {
+    ensure_not_running(&app_state)?;
+
+    let tested_offsets = app_state
+        .tested_offsets
+        .read()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| CommandError::Config("No offset suggestions to apply".to_string()))?;
+
+    let config = app_state.config_store.update(|config| {
+        let suggestions = suggestion::suggest_offsets(
+            &app_state.test_status.read().unwrap(),
+            &tested_offsets,
+            config.resolved_offset_model(),
+            include_passed,
+        );
+
+        let offsets: Vec<(usize, i32)> = suggestions
+            .iter()
+            .map(|suggestion| (suggestion.core_id, suggestion.suggested))
+            .collect();
+
+        // Apply the offsets at runtime, so the cores can be retested immediately
+        if config.applies_offsets_at_runtime() {
+            ryzen_smu::apply_offsets(&offsets).map_err(CommandError::BackendUnavailable)?;
+        }
+
+        for (core_id, offset) in offsets {
+            profile::set_offset(config, core_id, offset);
+        }
+        Ok(config.clone())
+    })?;
+
+    // Applying again would move the offsets another step without a new run
+    app_state.tested_offsets.write().unwrap().take();
+    Ok(config)
+}
+
+#[tauri::command]
+async fn get_smu_status() -> ryzen_smu::SmuStatus {
+    ryzen_smu::get_status()
+}
+
+/// Reads the offsets currently set in the SMU and compares them with the stored offsets
+#[tauri::command]
+async fn get_smu_offsets(
+    app_state: tauri::State<'_, AppState>,
+) -> Result<Vec<ryzen_smu::OffsetComparison>, CommandError> {
+    let config = app_state.config_store.load();
+    ryzen_smu::compare_offsets(&config.offset_per_core).map_err(CommandError::BackendUnavailable)
+}
+
+#[tauri::command]
+fn set_apply_offsets_via_smu(
+    enabled: bool,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<(), CommandError> {
+    ensure_not_running(&app_state)?;
+
+    app_state.config_store.update(|config| {
+        config.apply_offsets_via_smu = enabled;
+        Ok(())
+    })
+}
+
+/// Returns unit and range of the offsets, together with the available models
+#[tauri::command]
+async fn get_offset_model(
+    app_state: tauri::State<'_, AppState>,
+) -> Result<OffsetModelSettings, CommandError> {
+    let config = app_state.config_store.load();
+    Ok(offset_model::settings(config.offset_model))
+}
+
+/// Sets the offset model, None detects it from the CPU vendor
+/// The offsets of the previous model and the suggestions based on them are dropped
+#[tauri::command]
+fn set_offset_model(
+    model: Option<OffsetModel>,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<OffsetModelSettings, CommandError> {
+    ensure_not_running(&app_state)?;
+
+    app_state.config_store.update(|config| {
+        let previous_model = config.resolved_offset_model();
+        config.set_offset_model(model);
+        if config.resolved_offset_model() != previous_model {
+            app_state.tested_offsets.write().unwrap().take();
+        }
+        Ok(offset_model::settings(model))
+    })
+}
+
 #[tauri::command]
 fn set_offset(
     core_id: usize,
     offset: i32,
     app_state: tauri::State<'_, AppState>,
-) -> Result<(), ()> {
-    let mut config = config::load_config(&app_state.config_write_lock);
-    config.offset_per_core.insert(core_id, offset);
-    config::save_config(&config, &app_state.config_write_lock);
+) -> Result<(), CommandError> {
+    ensure_not_running(&app_state)?;
 
-    Ok(())
+    app_state.config_store.update(|config| {
+        config
+            .resolved_offset_model()
+            .validate(offset)
+            .map_err(CommandError::Config)?;
+
+        // Apply the offset at runtime, so the core can be retested immediately
+        if config.applies_offsets_at_runtime() {
+            ryzen_smu::apply_offsets(&[(core_id, offset)])
+                .map_err(CommandError::BackendUnavailable)?;
+        }
+
+        profile::set_offset(config, core_id, offset);
+        Ok(())
+    })
+}
+
+/// Stores the current offsets as profile, returns the updated config
+#[tauri::command]
+fn save_profile(
+    name: String,
+    bios_version: String,
+    pbo_limits: String,
+    notes: String,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<AppConfig, CommandError> {
+    ensure_not_running(&app_state)?;
+
+    let profile = profile::OffsetProfile {
+        name,
+        bios_version,
+        pbo_limits,
+        notes,
+        offset_per_core: HashMap::new(),
+    };
+
+    app_state.config_store.update(|config| {
+        profile::save_current(config, profile).map_err(CommandError::Config)?;
+        Ok(config.clone())
+    })
+}
+
+/// Switches to the given profile, returns the updated config
+#[tauri::command]
+fn switch_profile(
+    name: String,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<AppConfig, CommandError> {
+    ensure_not_running(&app_state)?;
+
+    app_state.config_store.update(|config| {
+        // The profile may have been saved with another offset model
+        let profile = profile::find(config, &name).map_err(CommandError::Config)?;
+        config
+            .resolved_offset_model()
+            .validate_all(&profile.offset_per_core)
+            .map_err(CommandError::Config)?;
+        profile::switch(config, &name).map_err(CommandError::Config)?;
+
+        // Apply the offsets of the profile at runtime
+        if config.applies_offsets_at_runtime() {
+            let offsets: Vec<(usize, i32)> = config
+                .offset_per_core
+                .iter()
+                .map(|(core_id, offset)| (*core_id, *offset))
+                .collect();
+            ryzen_smu::apply_offsets(&offsets).map_err(CommandError::BackendUnavailable)?;
+        }
+
+        Ok(config.clone())
+    })
+}
+
+#[tauri::command]
+fn delete_profile(
+    name: String,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<AppConfig, CommandError> {
+    ensure_not_running(&app_state)?;
+
+    app_state.config_store.update(|config| {
+        profile::delete(config, &name).map_err(CommandError::Config)?;
+        Ok(config.clone())
+    })
+}
+
+/// Imports a profile from a JSON or CSV file, returns the updated config
+#[tauri::command]
+fn import_profile(
+    path: String,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<AppConfig, CommandError> {
+    ensure_not_running(&app_state)?;
+
+    let imported = profile::import(std::path::Path::new(&path)).map_err(CommandError::Io)?;
+
+    app_state.config_store.update(|config| {
+        config
+            .resolved_offset_model()
+            .validate_all(&imported.offset_per_core)
+            .map_err(CommandError::Config)?;
+        profile::add_imported(config, imported);
+        Ok(config.clone())
+    })
+}
+
+/// Exports the given profile as JSON or CSV file, depending on the file extension
+#[tauri::command]
+fn export_profile(
+    name: String,
+    path: String,
+    app_state: tauri::State<'_, AppState>,
+) -> Result<(), CommandError> {
+    let config = app_state.config_store.load();
+    let profile = profile::find(&config, &name).map_err(CommandError::Config)?;
+    profile::export(profile, std::path::Path::new(&path)).map_err(CommandError::Io)
 }
@@ -1,56 +1,291 @@
-use crate::process;
+use crate::binary::{BinaryInfo, BinarySource};
+use crate::cpu_test::CpuTestMethod;
+use crate::{binary, process};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::process::{Child, Command};
 use std::thread;
 use std::time::Duration;
 
 pub const ERROR_MESSAGE: &str = "TORTURE TEST FAILED";
-const PROCESS_PATH: &str = "/tmp/pbo-assistant/mprime/mprime";
-const CONFIG_PATH: &str = "/tmp/pbo-assistant/mprime/prime.txt";
+const FFT_LENGTH_PREFIX: &str = "FFT length ";
+const SELF_TEST_PREFIX: &str = "Self-test ";
+const SELF_TEST_PASSED_SUFFIX: &str = " passed!";
+const MPRIME: &[u8] = include_bytes!("../../assets/mprime/mprime");
+const PRIME_TXT: &str = include_str!("../../assets/mprime/prime.txt");
+const WORKING_DIR_KEY: &str = "WorkingDir=";
 
-pub fn initialize() {
+lazy_static! {
+    static ref MPRIME_SHA256: String = binary::sha256(MPRIME);
+}
+
+/// The progress of the mprime torture test, parsed from its output
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Prime95Progress {
+    /// The FFT length that is currently tested, e.g. "1120K"
+    pub current_fft_length: Option<String>,
+    /// The distinct FFT lengths that passed at least once
+    pub completed_ffts: Vec<String>,
+    /// The total number of passed FFT self-tests
+    pub passes: u32,
+    /// The FFT length that was running when the torture test failed
+    pub failed_fft_length: Option<String>,
+}
+
+/// The mprime backend configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Prime95Config {
+    /// Path to an external mprime binary, if none the embedded one is used
+    pub binary_path: Option<String>,
+}
+
+/// Prepares mprime for the test run and returns the binary to use
+pub fn initialize(config: &Prime95Config) -> BinaryInfo {
     // Kill all processes
     process::kill();
 
-    let mprime = include_bytes!("../../assets/mprime/mprime");
-    let prime_txt = include_bytes!("../../assets/mprime/prime.txt");
+    extract();
+
+    let binary_info = resolve_binary(config);
+    print_binary_info(&binary_info);
+
+    binary_info
+}
+
+/// The versioned directory the embedded mprime is extracted to
+pub fn process_dir() -> PathBuf {
+    binary::version_dir("mprime", &MPRIME_SHA256)
+}
+
+fn process_path() -> PathBuf {
+    process_dir().join("mprime")
+}
+
+/// The working directory of the mprime instance testing the core
+/// Instances running at the same time must not share it, mprime keeps its state files in it
+fn instance_dir(core_id: usize) -> PathBuf {
+    process_dir().join(format!("core-{}", core_id))
+}
+
+fn config_path(working_dir: &Path) -> PathBuf {
+    working_dir.join("prime.txt")
+}
+
+/// Returns the embedded prime.txt with the working directory pointing to the given one
+fn prime_txt(working_dir: &Path) -> String {
+    PRIME_TXT
+        .lines()
+        .map(|line| {
+            if line.starts_with(WORKING_DIR_KEY) {
+                format!("{}{}/", WORKING_DIR_KEY, working_dir.display())
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Extracts the embedded mprime binary
+/// Reuses the binary of a previous run if it is still intact
+fn extract() {
+    binary::create_private_dir(&process_dir());
+
+    if verify_extracted().is_err() {
+        binary::write_verified(&process_path(), MPRIME, 0o700).expect("Failed to write file");
+    }
+}
 
-    std::fs::create_dir_all("/tmp/pbo-assistant/mprime").expect("Failed to create directory");
+/// Makes sure the extracted binary was not modified since the extraction
+fn verify_extracted() -> Result<(), String> {
+    binary::verify_file(&process_path(), &MPRIME_SHA256)
+}
 
-    std::fs::write(PROCESS_PATH, mprime).expect("Failed to write file");
-    std::fs::write("/tmp/pbo-assistant/mprime/prime.txt", prime_txt).expect("Failed to write file");
+/// Resolves the mprime binary to use, extracts the embedded binary if it is missing or modified
+pub fn resolve_binary(config: &Prime95Config) -> BinaryInfo {
+    binary::cached(
+        CpuTestMethod::Prime95,
+        &config.binary_path,
+        |binary_info| match binary_info.source {
+            BinarySource::Embedded => verify_extracted(),
+            BinarySource::External => Ok(()),
+        },
+        || {
+            extract();
 
-    Command::new("chmod")
-        .arg("+x")
-        .arg(PROCESS_PATH)
-        .output()
-        .expect("Failed to change permissions");
+            binary::resolve(
+                CpuTestMethod::Prime95,
+                &config.binary_path,
+                &process_path(),
+                &["-v"],
+            )
+        },
+    )
 }
 
-/// Start the process only with mprime -t prime.txt  
-fn spawn_process() -> Child {
-    let child_process = Command::new(PROCESS_PATH)
-        .arg("-t")
-        .arg(CONFIG_PATH)
-        .stdin(std::process::Stdio::piped())
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
-        .spawn()
-        .expect("Failed to start the process");
+pub fn print_binary_info(binary_info: &BinaryInfo) {
+    if let Some(warning) = &binary_info.warning {
+        println!("Warning: {}", warning);
+    }
+    println!(
+        "Using {:?} mprime {} ({})",
+        binary_info.source,
+        binary_info.version.as_deref().unwrap_or("unknown version"),
+        binary_info.path
+    );
+}
+
+/// Start the process only with mprime -t prime.txt in the working directory
+/// The configuration is always rewritten as mprime modifies it
+fn spawn_process(binary_path: &str, working_dir: &Path) -> Result<Child, String> {
+    binary::create_private_dir(working_dir);
+    binary::write_verified(
+        &config_path(working_dir),
+        prime_txt(working_dir).as_bytes(),
+        0o600,
+    )?;
+
+    let child_process = process::spawn_tracked(
+        Command::new(binary_path)
+            .arg("-t")
+            .arg(config_path(working_dir))
+            .current_dir(working_dir)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped()),
+    )
+    .map_err(|e| format!("Failed to start {}: {}", binary_path, e))?;
 
     let process_id = child_process.id();
 
     println!("Started process with id: {}", process_id);
 
-    child_process
+    Ok(child_process)
 }
 
-pub fn start_verification(core_id: usize) -> Child {
-    let child = spawn_process();
+/// Starts mprime on the given core, in a working directory of its own
+/// Refuses to start the embedded binary if it was modified since the extraction
+pub fn start_verification(core_id: usize, binary_info: &BinaryInfo) -> Result<Child, String> {
+    if binary_info.source == BinarySource::Embedded {
+        verify_extracted()?;
+    }
+
+    let child = spawn_process(&binary_info.path, &instance_dir(core_id))?;
 
     // Wait a second to make sure the process is started
     thread::sleep(Duration::from_secs(1));
 
     process::set_thread_affinity(child.id(), core_id);
 
-    child
+    Ok(child)
+}
+
+/// Updates the progress with the given mprime output line
+/// Returns true if the line changed the progress
+pub fn parse_progress_line(line: &str, progress: &mut Prime95Progress) -> bool {
+    // e.g. "Test 1, 12000 Lucas-Lehmer iterations of M21871519 using FMA3 FFT length 1120K, ..."
+    if let Some(index) = line.find(FFT_LENGTH_PREFIX) {
+        let fft_length = line[index + FFT_LENGTH_PREFIX.len()..]
+            .split([',', ' '])
+            .next()
+            .unwrap_or_default();
+
+        // Every iteration of the same FFT length is reported, only a new one is a change
+        if !fft_length.is_empty() {
+            let changed = progress.current_fft_length.as_deref() != Some(fft_length);
+            progress.current_fft_length = Some(fft_length.to_string());
+            return changed;
+        }
+    }
+
+    // e.g. "Self-test 1120K passed!"
+    if let Some(index) = line.find(SELF_TEST_PREFIX) {
+        if let Some(fft_length) = line[index + SELF_TEST_PREFIX.len()..]
+            .trim_end()
+            .strip_suffix(SELF_TEST_PASSED_SUFFIX)
+        {
+            progress.passes += 1;
+            if !progress.completed_ffts.iter().any(|fft| fft == fft_length) {
+                progress.completed_ffts.push(fft_length.to_string());
+            }
+            return true;
+        }
+    }
+
+    if line.contains(ERROR_MESSAGE) {
+        progress.failed_fft_length = progress.current_fft_length.clone();
+        return true;
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TORTURE_PASSED: &str = include_str!("../test_mprime_output/torture_passed");
+    const TORTURE_FAILED: &str = include_str!("../test_mprime_output/torture_failed");
+
+    #[test]
+    fn prime_txt_working_dir() {
+        // WHEN
+        let result = prime_txt(&instance_dir(3));
+
+        // THEN
+        assert!(result.contains(&format!("WorkingDir={}/core-3/", process_dir().display())));
+        assert!(result.contains("StressTester=1"));
+    }
+
+    #[test]
+    fn parse_progress_line_passed() {
+        // GIVEN
+        let mut progress = Prime95Progress::default();
+
+        // WHEN
+        for line in TORTURE_PASSED.lines() {
+            parse_progress_line(line, &mut progress);
+        }
+
+        // THEN
+        assert_eq!(
+            progress,
+            Prime95Progress {
+                current_fft_length: Some("10K".to_string()),
+                completed_ffts: vec!["8960K".to_string(), "9216K".to_string()],
+                passes: 3,
+                failed_fft_length: None,
+            }
+        );
+    }
+
+    #[test]
+    fn parse_progress_line_failed() {
+        // GIVEN
+        let mut progress = Prime95Progress::default();
+
+        // WHEN
+        for line in TORTURE_FAILED.lines() {
+            parse_progress_line(line, &mut progress);
+        }
+
+        // THEN
+        assert_eq!(progress.completed_ffts, vec!["8960K".to_string()]);
+        assert_eq!(progress.passes, 1);
+        assert_eq!(progress.failed_fft_length, Some("9216K".to_string()));
+    }
+
+    #[test]
+    fn parse_progress_line_unrelated() {
+        // GIVEN
+        let mut progress = Prime95Progress::default();
+
+        // WHEN
+        let result = parse_progress_line("[Main thread] Starting workers.", &mut progress);
+
+        // THEN
+        assert!(!result);
+        assert_eq!(progress, Prime95Progress::default());
+    }
 }
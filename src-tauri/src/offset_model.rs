@@ -0,0 +1,212 @@
+use crate::cpu_info::{self, CpuVendor};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter, EnumString};
+
+/// How the per-core offsets are interpreted, depends on the CPU vendor
+#[derive(
+    Debug, Clone, Copy, Eq, PartialEq, Hash, EnumIter, Display, EnumString, Serialize, Deserialize,
+)]
+pub enum OffsetModel {
+    /// AMD Curve Optimizer counts
+    CurveOptimizer,
+    /// Intel per-core voltage offset in mV
+    VoltageOffset,
+    /// Intel per-core ratio offset in multiplier bins
+    CoreRatio,
+}
+
+/// Unit, range and step of an offset model, as used by the UI
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OffsetModelInfo {
+    pub model: OffsetModel,
+    pub unit: &'static str,
+    pub min: i32,
+    pub max: i32,
+    pub step: i32,
+    /// Whether the offsets can be applied at runtime through ryzen_smu
+    pub runtime_apply: bool,
+}
+
+/// The active offset model with the available ones, as shown in the UI
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OffsetModelSettings {
+    pub active: OffsetModelInfo,
+    /// None if the model is detected from the CPU vendor
+    pub configured: Option<OffsetModel>,
+    pub models: Vec<OffsetModel>,
+}
+
+impl OffsetModel {
+    /// The default model for CPUs of the given vendor
+    pub fn for_vendor(vendor: CpuVendor) -> OffsetModel {
+        match vendor {
+            CpuVendor::Intel => OffsetModel::VoltageOffset,
+            CpuVendor::Amd | CpuVendor::Unknown => OffsetModel::CurveOptimizer,
+        }
+    }
+
+    pub fn info(&self) -> OffsetModelInfo {
+        let (unit, min, max, step) = match self {
+            OffsetModel::CurveOptimizer => ("counts", -30, 30, 1),
+            OffsetModel::VoltageOffset => ("mV", -250, 250, 5),
+            OffsetModel::CoreRatio => ("x", -20, 20, 1),
+        };
+
+        OffsetModelInfo {
+            model: *self,
+            unit,
+            min,
+            max,
+            step,
+            runtime_apply: *self == OffsetModel::CurveOptimizer,
+        }
+    }
+
+    /// Checks if the offset is within the range and a multiple of the step
+    pub fn validate(&self, offset: i32) -> Result<(), String> {
+        let info = self.info();
+
+        if offset < info.min || offset > info.max {
+            return Err(format!(
+                "Offset {} {} is out of range for {} ({} to {})",
+                offset, info.unit, self, info.min, info.max
+            ));
+        }
+
+        if offset % info.step != 0 {
+            return Err(format!(
+                "Offset {} {} is not a multiple of {} for {}",
+                offset, info.unit, info.step, self
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Checks the offsets of all cores
+    pub fn validate_all(&self, offset_per_core: &HashMap<usize, i32>) -> Result<(), String> {
+        let mut core_ids: Vec<&usize> = offset_per_core.keys().collect();
+        core_ids.sort();
+
+        for core_id in core_ids {
+            self.validate(offset_per_core[core_id])
+                .map_err(|e| format!("Core {}: {}", core_id, e))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn clamp(&self, offset: i32) -> i32 {
+        let info = self.info();
+        offset.clamp(info.min, info.max)
+    }
+
+    /// The change towards stability, e.g. after a failed test
+    /// A less negative voltage offset is more stable, a lower ratio too
+    pub fn stabilizing_step(&self) -> i32 {
+        match self {
+            OffsetModel::CurveOptimizer => 2,
+            OffsetModel::VoltageOffset => 10,
+            OffsetModel::CoreRatio => -1,
+        }
+    }
+
+    /// The change towards more performance or efficiency, e.g. after a passed test
+    pub fn tightening_step(&self) -> i32 {
+        -self.info().step * self.stabilizing_step().signum()
+    }
+}
+
+/// Returns the configured offset model or detects it from the CPU vendor
+pub fn resolve(configured: Option<OffsetModel>) -> OffsetModel {
+    configured.unwrap_or_else(|| {
+        let vendor = cpu_info::get()
+            .map(|cpus_info| cpus_info.vendor)
+            .unwrap_or(CpuVendor::Unknown);
+        OffsetModel::for_vendor(vendor)
+    })
+}
+
+pub fn settings(configured: Option<OffsetModel>) -> OffsetModelSettings {
+    OffsetModelSettings {
+        active: resolve(configured).info(),
+        configured,
+        models: OffsetModel::iter().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn for_vendor_intel() {
+        // GIVEN
+        let vendor = CpuVendor::Intel;
+
+        // WHEN
+        let result = OffsetModel::for_vendor(vendor);
+
+        // THEN
+        assert_eq!(result, OffsetModel::VoltageOffset);
+    }
+
+    #[test]
+    fn validate_curve_optimizer_out_of_range() {
+        // GIVEN
+        let model = OffsetModel::CurveOptimizer;
+
+        // WHEN
+        let result = model.validate(-31);
+
+        // THEN
+        assert!(result.unwrap_err().contains("out of range"));
+    }
+
+    #[test]
+    fn validate_voltage_offset_step() {
+        // GIVEN
+        let model = OffsetModel::VoltageOffset;
+
+        // WHEN
+        let valid = model.validate(-125);
+        let invalid = model.validate(-123);
+
+        // THEN
+        assert!(valid.is_ok());
+        assert!(invalid.unwrap_err().contains("multiple of 5"));
+    }
+
+    #[test]
+    fn validate_all_names_core() {
+        // GIVEN
+        let offset_per_core = HashMap::from([(0, -20), (4, -40)]);
+
+        // WHEN
+        let result = OffsetModel::CurveOptimizer.validate_all(&offset_per_core);
+
+        // THEN
+        assert!(result.unwrap_err().starts_with("Core 4:"));
+    }
+
+    #[test]
+    fn steps_per_model() {
+        // GIVEN
+        let models = [
+            OffsetModel::CurveOptimizer,
+            OffsetModel::VoltageOffset,
+            OffsetModel::CoreRatio,
+        ];
+
+        // WHEN
+        let result: Vec<(i32, i32)> = models
+            .iter()
+            .map(|model| (model.stabilizing_step(), model.tightening_step()))
+            .collect();
+
+        // THEN
+        assert_eq!(result, vec![(2, -1), (10, -5), (-1, 1)]);
+    }
+}
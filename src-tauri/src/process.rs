@@ -1,5 +1,19 @@
 use crate::cpu_info;
-use std::process::Command;
+use lazy_static::lazy_static;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, Read};
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, Stdio};
+use std::sync::RwLock;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+lazy_static! {
+    /// The test program processes that were started and not waited for yet
+    /// Each leads its own process group, which includes the workers it started
+    static ref CHILD_PIDS: RwLock<HashSet<u32>> = RwLock::new(HashSet::new());
+}
 
 /// Set the affinity of a thread to a specific core by using the `taskset` command
 pub fn set_thread_affinity(pid: u32, physical_core_id: usize) {
@@ -19,19 +33,172 @@ pub fn set_thread_affinity(pid: u32, physical_core_id: usize) {
         .expect("Failed to set thread affinity");
 }
 
+/// Pins the calling thread to the first logical core of the physical core, e.g. for the built-in methods
+pub fn pin_current_thread(physical_core_id: usize) -> Result<(), String> {
+    let logical_core_id = cpu_info::get_first_logical_core_id_for(physical_core_id);
+
+    // The link points to <pid>/task/<tid>
+    let thread_self = fs::read_link("/proc/thread-self")
+        .map_err(|e| format!("Failed to get the thread id: {}", e))?;
+    let thread_id = thread_self
+        .file_name()
+        .map(|thread_id| thread_id.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Invalid thread path {}", thread_self.display()))?;
+
+    let status = Command::new("taskset")
+        .arg("-cp")
+        .arg(logical_core_id.to_string())
+        .arg(&thread_id)
+        .stdout(Stdio::null())
+        .status()
+        .map_err(|e| format!("Failed to run taskset: {}", e))?;
+    if !status.success() {
+        return Err(format!(
+            "Failed to pin thread {} to logical core {}",
+            thread_id, logical_core_id
+        ));
+    }
+
+    Ok(())
+}
+
+/// Starts the test program in a process group of its own and tracks it,
+/// so that it is reached by `kill`, `pause` and `resume` together with its workers
+pub fn spawn_tracked(command: &mut Command) -> io::Result<Child> {
+    let child = command.process_group(0).spawn()?;
+    CHILD_PIDS.write().unwrap().insert(child.id());
+    Ok(child)
+}
+
+/// Waits for the test program to exit and stops tracking it
+/// The pid stays reserved until the process is waited for, so it cannot be reused in between
+pub fn wait(mut child: Child) {
+    let _ = child.wait();
+    CHILD_PIDS.write().unwrap().remove(&child.id());
+}
+
+/// Kills all started test programs
 pub fn kill() {
-    let output = Command::new("pgrep")
-        .arg("-f")
-        .arg("/tmp/pbo-assistant/")
-        .output()
-        .expect("Failed to list processes");
+    signal("TERM");
+}
 
-    let pids = String::from_utf8(output.stdout).expect("Failed to convert output to string");
+/// Suspends all started test programs, they keep their state until resumed
+pub fn pause() {
+    signal("STOP");
+}
 
-    for pid in pids.lines() {
+/// Continues all test programs suspended by `pause`
+pub fn resume() {
+    signal("CONT");
+}
+
+/// Sends the signal to the process groups of the started test programs only,
+/// never to other processes of the user
+fn signal(signal: &str) {
+    for pid in CHILD_PIDS.read().unwrap().iter() {
+        // The group id is the pid of the test program, a negative pid addresses the group
         Command::new("kill")
-            .arg(pid)
+            .arg(format!("-{}", signal))
+            .arg("--")
+            .arg(format!("-{}", pid))
             .output()
-            .expect("Failed to kill process");
+            .expect("Failed to signal process");
+    }
+}
+
+/// Runs the given binary and returns its stdout and stderr output
+/// The process is killed if it does not exit within the timeout
+pub fn run_with_timeout(path: &str, args: &[&str], timeout: Duration) -> Option<String> {
+    let mut child = Command::new(path)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .ok()?;
+
+    // Read both pipes while waiting, a full pipe would block the process until the timeout
+    let stdout_reader = read_in_background(child.stdout.take());
+    let stderr_reader = read_in_background(child.stderr.take());
+
+    let start_time = Instant::now();
+    while child.try_wait().ok()?.is_none() {
+        if start_time.elapsed() > timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    let mut output = stdout_reader.join().unwrap_or_default();
+    output.push_str(&stderr_reader.join().unwrap_or_default());
+    Some(output)
+}
+
+/// Reads the pipe until it is closed, e.g. because the process exited
+fn read_in_background(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<String> {
+    thread::spawn(move || {
+        let mut output = String::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_string(&mut output);
+        }
+        output
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_with_timeout_reads_output_larger_than_pipe() {
+        // GIVEN
+        let script = "yes | head -c 200000";
+
+        // WHEN
+        let start_time = Instant::now();
+        let result = run_with_timeout("sh", &["-c", script], Duration::from_secs(5));
+
+        // THEN
+        assert_eq!(result.unwrap().len(), 200000);
+        assert!(start_time.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn pause_reaches_workers_of_test_program() {
+        // GIVEN
+        let child =
+            spawn_tracked(Command::new("sh").args(["-c", "sleep 30 & sleep 30 & wait"])).unwrap();
+        let process_group = child.id();
+        let start_time = Instant::now();
+        while process_states(process_group).len() < 3 && start_time.elapsed().as_secs() < 5 {
+            thread::sleep(Duration::from_millis(50));
+        }
+
+        // WHEN
+        pause();
+        thread::sleep(Duration::from_millis(200));
+        let paused_states = process_states(process_group);
+        resume();
+        kill();
+        wait(child);
+
+        // THEN
+        assert_eq!(paused_states, vec!['T', 'T', 'T']);
+    }
+
+    /// Returns the states of the processes in the group, e.g. 'T' if stopped
+    fn process_states(process_group: u32) -> Vec<char> {
+        fs::read_dir("/proc")
+            .unwrap()
+            .filter_map(|entry| fs::read_to_string(entry.ok()?.path().join("stat")).ok())
+            .filter_map(|stat| {
+                // e.g. "42 (sleep) S 41 41 ...", the fields after the name are state, ppid and pgrp
+                let fields: Vec<&str> = stat[stat.rfind(')')? + 1..].split_whitespace().collect();
+                let state = fields.first()?.chars().next()?;
+                (fields.get(2)?.parse::<u32>().ok()? == process_group).then_some(state)
+            })
+            .collect()
     }
 }
@@ -0,0 +1,424 @@
+use crate::config::AppConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+const CSV_PROFILE_KEY: &str = "Profile";
+const CSV_BIOS_VERSION_KEY: &str = "BIOS Version";
+const CSV_PBO_LIMITS_KEY: &str = "PBO Limits";
+const CSV_NOTES_KEY: &str = "Notes";
+const CSV_CORE_HEADER: &str = "Core";
+const CSV_OFFSET_HEADER: &str = "Curve Optimizer";
+
+/// A named set of offsets, e.g. per machine or per BIOS version
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OffsetProfile {
+    pub name: String,
+    #[serde(default)]
+    pub bios_version: String,
+    #[serde(default)]
+    pub pbo_limits: String,
+    #[serde(default)]
+    pub notes: String,
+    pub offset_per_core: HashMap<usize, i32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProfileFormat {
+    Json,
+    Csv,
+}
+
+impl ProfileFormat {
+    /// Determines the format by the file extension, defaults to JSON
+    pub fn from_path(path: &Path) -> ProfileFormat {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("csv") => ProfileFormat::Csv,
+            _ => ProfileFormat::Json,
+        }
+    }
+}
+
+/// Stores the current offsets as profile with the given metadata and makes it the active one
+/// An existing profile with the same name is replaced
+pub fn save_current(config: &mut AppConfig, mut profile: OffsetProfile) -> Result<(), String> {
+    if profile.name.trim().is_empty() {
+        return Err("Profile name must not be empty".to_string());
+    }
+
+    profile.offset_per_core = config.offset_per_core.clone();
+    config.active_profile = Some(profile.name.clone());
+    add(config, profile);
+
+    Ok(())
+}
+
+/// Adds the profile, replacing an existing profile with the same name
+fn add(config: &mut AppConfig, profile: OffsetProfile) {
+    match config
+        .profiles
+        .iter_mut()
+        .find(|existing| existing.name == profile.name)
+    {
+        Some(existing) => *existing = profile,
+        None => config.profiles.push(profile),
+    }
+}
+
+/// Adds an imported profile, renamed if the name is taken, e.g. "Bench 1 (2)"
+/// Existing profiles are never replaced, so the active profile keeps matching the current offsets
+pub fn add_imported(config: &mut AppConfig, mut profile: OffsetProfile) {
+    let name = profile.name.clone();
+    let mut suffix = 2;
+    while find(config, &profile.name).is_ok() {
+        profile.name = format!("{} ({})", name, suffix);
+        suffix += 1;
+    }
+
+    config.profiles.push(profile);
+}
+
+/// Makes the given profile the active one and takes over its offsets
+pub fn switch(config: &mut AppConfig, name: &str) -> Result<(), String> {
+    let profile = find(config, name)?;
+
+    config.offset_per_core = profile.offset_per_core.clone();
+    config.active_profile = Some(name.to_string());
+
+    Ok(())
+}
+
+/// Removes the given profile, the current offsets are kept
+pub fn delete(config: &mut AppConfig, name: &str) -> Result<(), String> {
+    find(config, name)?;
+
+    config.profiles.retain(|profile| profile.name != name);
+    if config.active_profile.as_deref() == Some(name) {
+        config.active_profile = None;
+    }
+
+    Ok(())
+}
+
+/// Sets the offset of the core, also in the active profile
+pub fn set_offset(config: &mut AppConfig, core_id: usize, offset: i32) {
+    config.offset_per_core.insert(core_id, offset);
+
+    let active_profile = config.active_profile.clone();
+    if let Some(profile) = config
+        .profiles
+        .iter_mut()
+        .find(|profile| Some(&profile.name) == active_profile.as_ref())
+    {
+        profile.offset_per_core.insert(core_id, offset);
+    }
+}
+
+/// Returns the profile with the given name
+pub fn find<'a>(config: &'a AppConfig, name: &str) -> Result<&'a OffsetProfile, String> {
+    config
+        .profiles
+        .iter()
+        .find(|profile| profile.name == name)
+        .ok_or(format!("Profile {} does not exist", name))
+}
+
+/// Reads a profile from a JSON or CSV file
+/// If the file contains no profile name, the file name is used
+pub fn import(path: &Path) -> Result<OffsetProfile, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let mut profile = match ProfileFormat::from_path(path) {
+        ProfileFormat::Json => serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid profile {}: {}", path.display(), e))?,
+        ProfileFormat::Csv => from_csv(&content)?,
+    };
+
+    if profile.name.is_empty() {
+        profile.name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+    }
+
+    Ok(profile)
+}
+
+/// Writes the profile as JSON or CSV file, depending on the file extension
+pub fn export(profile: &OffsetProfile, path: &Path) -> Result<(), String> {
+    let content = match ProfileFormat::from_path(path) {
+        ProfileFormat::Json => serde_json::to_string_pretty(profile).unwrap(),
+        ProfileFormat::Csv => to_csv(profile),
+    };
+
+    std::fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Formats the profile as CSV, the metadata as key value rows followed by one row per core
+fn to_csv(profile: &OffsetProfile) -> String {
+    let mut lines = vec![
+        format!("{},{}", CSV_PROFILE_KEY, quote_csv(&profile.name)),
+        format!(
+            "{},{}",
+            CSV_BIOS_VERSION_KEY,
+            quote_csv(&profile.bios_version)
+        ),
+        format!("{},{}", CSV_PBO_LIMITS_KEY, quote_csv(&profile.pbo_limits)),
+        format!("{},{}", CSV_NOTES_KEY, quote_csv(&profile.notes)),
+        format!("{},{}", CSV_CORE_HEADER, CSV_OFFSET_HEADER),
+    ];
+
+    let mut core_ids: Vec<&usize> = profile.offset_per_core.keys().collect();
+    core_ids.sort();
+    for core_id in core_ids {
+        lines.push(format!("{},{}", core_id, profile.offset_per_core[core_id]));
+    }
+
+    lines.join("\n") + "\n"
+}
+
+/// Parses a profile from CSV
+/// Accepts the core as number or Ryzen Master style label, e.g. "Core 3"
+fn from_csv(content: &str) -> Result<OffsetProfile, String> {
+    let mut profile = OffsetProfile::default();
+
+    for (index, line) in content.lines().enumerate() {
+        let fields = split_csv(line);
+        if fields.len() < 2 {
+            continue;
+        }
+
+        let key = fields[0].trim();
+        let value = fields[1].trim();
+
+        match key {
+            CSV_PROFILE_KEY => profile.name = value.to_string(),
+            CSV_BIOS_VERSION_KEY => profile.bios_version = value.to_string(),
+            CSV_PBO_LIMITS_KEY => profile.pbo_limits = value.to_string(),
+            CSV_NOTES_KEY => profile.notes = value.to_string(),
+            CSV_CORE_HEADER => {}
+            _ => {
+                let core_id = key
+                    .trim_start_matches(CSV_CORE_HEADER)
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|e| format!("Invalid core in line {}: {}: {}", index + 1, key, e))?;
+                let offset = value.parse::<i32>().map_err(|e| {
+                    format!("Invalid offset in line {}: {}: {}", index + 1, value, e)
+                })?;
+                profile.offset_per_core.insert(core_id, offset);
+            }
+        }
+    }
+
+    Ok(profile)
+}
+
+/// Quotes the CSV field if it contains a separator or quote
+/// Line breaks are replaced, as every row has to be a single line
+fn quote_csv(field: &str) -> String {
+    let field = field.replace(['\r', '\n'], " ");
+    if field.contains([',', '"']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+/// Splits the CSV line into its fields, respecting quoted fields
+fn split_csv(line: &str) -> Vec<String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config() -> AppConfig {
+        AppConfig {
+            offset_per_core: HashMap::from([(0, -10)]),
+            ..Default::default()
+        }
+    }
+
+    fn test_profile() -> OffsetProfile {
+        OffsetProfile {
+            name: "Bench 1".to_string(),
+            bios_version: "AGESA 1.2.0.2".to_string(),
+            pbo_limits: "PPT 142, TDC 95, EDC 140".to_string(),
+            notes: "Core 3 is \"sensitive\"".to_string(),
+            offset_per_core: HashMap::from([(0, -20), (3, -8), (11, -25)]),
+        }
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        // GIVEN
+        let profile = test_profile();
+
+        // WHEN
+        let result = from_csv(&to_csv(&profile));
+
+        // THEN
+        assert_eq!(result.unwrap(), profile);
+    }
+
+    #[test]
+    fn from_csv_ryzen_master_style() {
+        // GIVEN
+        let csv = "Core,Curve Optimizer\nCore 0,-15\nCore 1, -10\n";
+
+        // WHEN
+        let result = from_csv(csv);
+
+        // THEN
+        let profile = result.unwrap();
+        assert_eq!(profile.offset_per_core, HashMap::from([(0, -15), (1, -10)]));
+        assert_eq!(profile.name, "");
+    }
+
+    #[test]
+    fn from_csv_invalid_offset() {
+        // GIVEN
+        let csv = "Core,Curve Optimizer\n0,abc\n";
+
+        // WHEN
+        let result = from_csv(csv);
+
+        // THEN
+        assert!(result.unwrap_err().contains("line 2"));
+    }
+
+    #[test]
+    fn export_import_json() {
+        // GIVEN
+        let profile = test_profile();
+        let path = std::env::temp_dir().join("pbo-assistant-profile-test.json");
+
+        // WHEN
+        export(&profile, &path).unwrap();
+        let result = import(&path);
+
+        // THEN
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result.unwrap(), profile);
+    }
+
+    #[test]
+    fn import_csv_without_name_uses_file_name() {
+        // GIVEN
+        let path = std::env::temp_dir().join("pbo-assistant-profile-test.csv");
+        std::fs::write(&path, "Core,Curve Optimizer\n0,-5\n").unwrap();
+
+        // WHEN
+        let result = import(&path);
+
+        // THEN
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(result.unwrap().name, "pbo-assistant-profile-test");
+    }
+
+    #[test]
+    fn switch_takes_over_offsets() {
+        // GIVEN
+        let mut config = test_config();
+        let profile = OffsetProfile {
+            name: "BIOS 1.2".to_string(),
+            ..Default::default()
+        };
+        save_current(&mut config, profile).unwrap();
+        config.active_profile = None;
+        config.offset_per_core = HashMap::from([(0, -25), (1, -5)]);
+
+        // WHEN
+        let result = switch(&mut config, "BIOS 1.2");
+
+        // THEN
+        assert!(result.is_ok());
+        assert_eq!(config.offset_per_core, HashMap::from([(0, -10)]));
+        assert_eq!(config.active_profile, Some("BIOS 1.2".to_string()));
+    }
+
+    #[test]
+    fn set_offset_updates_active_profile() {
+        // GIVEN
+        let mut config = test_config();
+        add(&mut config, test_profile());
+        switch(&mut config, "Bench 1").unwrap();
+
+        // WHEN
+        set_offset(&mut config, 3, -12);
+
+        // THEN
+        assert_eq!(config.offset_per_core[&3], -12);
+        assert_eq!(config.profiles[0].offset_per_core[&3], -12);
+    }
+
+    #[test]
+    fn delete_active_profile() {
+        // GIVEN
+        let mut config = test_config();
+        add(&mut config, test_profile());
+        switch(&mut config, "Bench 1").unwrap();
+
+        // WHEN
+        let result = delete(&mut config, "Bench 1");
+
+        // THEN
+        assert!(result.is_ok());
+        assert!(config.profiles.is_empty());
+        assert_eq!(config.active_profile, None);
+        assert_eq!(config.offset_per_core[&3], -8);
+    }
+
+    #[test]
+    fn add_imported_renames_duplicate() {
+        // GIVEN
+        let mut config = test_config();
+        save_current(&mut config, test_profile()).unwrap();
+        add_imported(&mut config, test_profile());
+
+        // WHEN
+        add_imported(&mut config, test_profile());
+
+        // THEN
+        let names: Vec<&str> = config
+            .profiles
+            .iter()
+            .map(|profile| profile.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Bench 1", "Bench 1 (2)", "Bench 1 (3)"]);
+        assert_eq!(config.profiles[0].offset_per_core, config.offset_per_core);
+    }
+
+    #[test]
+    fn switch_unknown_profile() {
+        // GIVEN
+        let mut config = test_config();
+
+        // WHEN
+        let result = switch(&mut config, "Unknown");
+
+        // THEN
+        assert!(result.unwrap_err().contains("does not exist"));
+    }
+}
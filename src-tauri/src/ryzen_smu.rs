@@ -0,0 +1,505 @@
+use crate::cpu_info;
+use crate::offset_model::OffsetModel;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const SYSFS_PATH: &str = "/sys/kernel/ryzen_smu_drv";
+const SMU_ARGS_FILE: &str = "smu_args";
+const RSMU_CMD_FILE: &str = "rsmu_cmd";
+const VERSION_FILE: &str = "version";
+const SMU_ARGS_COUNT: usize = 6;
+const SMU_STATUS_OK: u32 = 0x01;
+const CORES_PER_CCD: usize = 8;
+
+/// The RSMU commands to read and write the Curve Optimizer offset of a single core
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct SmuCommands {
+    pub family_name: &'static str,
+    pub set_offset: u32,
+    pub get_offset: u32,
+}
+
+const ZEN3_COMMANDS: SmuCommands = SmuCommands {
+    family_name: "Zen 3 (Vermeer)",
+    set_offset: 0x0A,
+    get_offset: 0x7C,
+};
+
+const ZEN4_COMMANDS: SmuCommands = SmuCommands {
+    family_name: "Zen 4 (Raphael)",
+    set_offset: 0x06,
+    get_offset: 0xD5,
+};
+
+const ZEN5_COMMANDS: SmuCommands = SmuCommands {
+    family_name: "Zen 5 (Granite Ridge)",
+    set_offset: 0x06,
+    get_offset: 0xD5,
+};
+
+/// Returns the SMU commands for the given cpu family and model
+pub fn get_commands(family: u32, model: u32) -> Option<SmuCommands> {
+    match (family, model) {
+        (0x19, 0x21) => Some(ZEN3_COMMANDS),
+        (0x19, 0x61) => Some(ZEN4_COMMANDS),
+        (0x1A, 0x44) => Some(ZEN5_COMMANDS),
+        _ => None,
+    }
+}
+
+/// Describes if offsets can be applied at runtime on this machine
+#[derive(Debug, Clone, Serialize)]
+pub struct SmuStatus {
+    pub available: bool,
+    pub family_name: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Checks if the ryzen_smu driver is loaded and the cpu family is supported
+pub fn get_status() -> SmuStatus {
+    match CurveOptimizer::open() {
+        Ok(curve_optimizer) => SmuStatus {
+            available: true,
+            family_name: Some(curve_optimizer.commands().family_name.to_string()),
+            error: None,
+        },
+        Err(error) => SmuStatus {
+            available: false,
+            family_name: None,
+            error: Some(error),
+        },
+    }
+}
+
+/// Applies the offsets of the given physical cores through the ryzen_smu driver
+pub fn apply_offsets(offsets: &[(usize, i32)]) -> Result<(), String> {
+    let curve_optimizer = CurveOptimizer::open()?;
+
+    for (physical_core_id, offset) in offsets {
+        let proc_core_id = cpu_info::get_proc_core_id_for(*physical_core_id);
+        curve_optimizer.set_offset(proc_core_id, *offset)?;
+        println!(
+            "Applied Curve Optimizer offset {} to core {}",
+            offset, physical_core_id
+        );
+    }
+
+    Ok(())
+}
+
+/// The offset of a core stored in the config compared to the one currently set in the SMU
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OffsetComparison {
+    pub core_id: usize,
+    pub stored: i32,
+    pub actual: i32,
+    pub differs: bool,
+}
+
+/// Reads the current offsets of all physical cores through the ryzen_smu driver
+/// and compares them with the stored offsets
+pub fn compare_offsets(stored: &HashMap<usize, i32>) -> Result<Vec<OffsetComparison>, String> {
+    let curve_optimizer = CurveOptimizer::open()?;
+
+    let cores: Vec<(usize, usize)> = cpu_info::get()?
+        .cpus
+        .iter()
+        .map(|cpu| (cpu.id, cpu.proc_core_id))
+        .collect();
+
+    let actual = curve_optimizer.read_offsets(&cores)?;
+
+    Ok(build_comparison(stored, &actual))
+}
+
+/// Compares the stored with the actual offsets, cores without stored offset are expected at 0
+fn build_comparison(
+    stored: &HashMap<usize, i32>,
+    actual: &HashMap<usize, i32>,
+) -> Vec<OffsetComparison> {
+    let mut comparison: Vec<OffsetComparison> = actual
+        .iter()
+        .map(|(core_id, actual)| {
+            let stored = *stored.get(core_id).unwrap_or(&0);
+            OffsetComparison {
+                core_id: *core_id,
+                stored,
+                actual: *actual,
+                differs: stored != *actual,
+            }
+        })
+        .collect();
+
+    comparison.sort_by_key(|entry| entry.core_id);
+    comparison
+}
+
+/// Sends commands to the SMU
+pub trait SmuTransport {
+    /// Sends the command with the given arguments and returns the response arguments
+    fn send(
+        &self,
+        command: u32,
+        args: [u32; SMU_ARGS_COUNT],
+    ) -> Result<[u32; SMU_ARGS_COUNT], String>;
+}
+
+/// Sends commands to the SMU through the sysfs interface of the ryzen_smu driver
+pub struct SysfsTransport {
+    root: PathBuf,
+}
+
+impl SysfsTransport {
+    pub fn new(root: &Path) -> Result<SysfsTransport, String> {
+        let version = fs::read_to_string(root.join(VERSION_FILE)).map_err(|e| {
+            format!(
+                "ryzen_smu driver is not available at {}: {}",
+                root.display(),
+                e
+            )
+        })?;
+        println!("Using ryzen_smu driver version {}", version.trim());
+
+        Ok(SysfsTransport {
+            root: root.to_path_buf(),
+        })
+    }
+}
+
+impl SmuTransport for SysfsTransport {
+    fn send(
+        &self,
+        command: u32,
+        args: [u32; SMU_ARGS_COUNT],
+    ) -> Result<[u32; SMU_ARGS_COUNT], String> {
+        let args_path = self.root.join(SMU_ARGS_FILE);
+        let cmd_path = self.root.join(RSMU_CMD_FILE);
+
+        let args_bytes: Vec<u8> = args.iter().flat_map(|arg| arg.to_le_bytes()).collect();
+        fs::write(&args_path, args_bytes)
+            .map_err(|e| format!("Failed to write {}: {}", args_path.display(), e))?;
+        fs::write(&cmd_path, command.to_le_bytes())
+            .map_err(|e| format!("Failed to write {}: {}", cmd_path.display(), e))?;
+
+        let status = read_u32s(&cmd_path)?;
+        if status.first() != Some(&SMU_STATUS_OK) {
+            return Err(format!(
+                "SMU rejected command {:#x} with status {:#x}",
+                command,
+                status.first().unwrap_or(&0)
+            ));
+        }
+
+        let response = read_u32s(&args_path)?;
+        let mut response_args = [0; SMU_ARGS_COUNT];
+        for (index, value) in response.iter().take(SMU_ARGS_COUNT).enumerate() {
+            response_args[index] = *value;
+        }
+
+        Ok(response_args)
+    }
+}
+
+/// Reads the file as a list of little endian u32 values
+fn read_u32s(path: &Path) -> Result<Vec<u32>, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect())
+}
+
+/// Reads and writes per-core Curve Optimizer offsets through the SMU
+pub struct CurveOptimizer<T: SmuTransport> {
+    transport: T,
+    commands: SmuCommands,
+}
+
+impl CurveOptimizer<SysfsTransport> {
+    /// Creates a Curve Optimizer backend for the current cpu using the ryzen_smu driver
+    pub fn open() -> Result<CurveOptimizer<SysfsTransport>, String> {
+        let cpus_info = cpu_info::get()?;
+        let commands = get_commands(cpus_info.family, cpus_info.model).ok_or(format!(
+            "Curve Optimizer is not supported for cpu family {:#x} model {:#x}",
+            cpus_info.family, cpus_info.model
+        ))?;
+
+        let transport = SysfsTransport::new(Path::new(SYSFS_PATH))?;
+
+        Ok(CurveOptimizer::new(transport, commands))
+    }
+}
+
+impl<T: SmuTransport> CurveOptimizer<T> {
+    pub fn new(transport: T, commands: SmuCommands) -> CurveOptimizer<T> {
+        CurveOptimizer {
+            transport,
+            commands,
+        }
+    }
+
+    pub fn commands(&self) -> SmuCommands {
+        self.commands
+    }
+
+    /// Writes the offset of the core and verifies it by reading it back
+    /// The proc_core_id is the "core id" of /proc/cpuinfo
+    pub fn set_offset(&self, proc_core_id: usize, offset: i32) -> Result<(), String> {
+        OffsetModel::CurveOptimizer.validate(offset)?;
+
+        let arg = core_mask(proc_core_id) | (offset as u32 & 0xFFFF);
+        self.transport
+            .send(self.commands.set_offset, [arg, 0, 0, 0, 0, 0])?;
+
+        let actual_offset = self.get_offset(proc_core_id)?;
+        if actual_offset != offset {
+            return Err(format!(
+                "Offset verification failed for core {}: expected {}, got {}",
+                proc_core_id, offset, actual_offset
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Reads the current offsets of the given cores
+    /// Each core is given as tuple of the physical core id and the "core id" of /proc/cpuinfo
+    pub fn read_offsets(&self, cores: &[(usize, usize)]) -> Result<HashMap<usize, i32>, String> {
+        let mut offsets = HashMap::new();
+
+        for (physical_core_id, proc_core_id) in cores {
+            offsets.insert(*physical_core_id, self.get_offset(*proc_core_id)?);
+        }
+
+        Ok(offsets)
+    }
+
+    /// Reads the current offset of the core
+    pub fn get_offset(&self, proc_core_id: usize) -> Result<i32, String> {
+        let response = self.transport.send(
+            self.commands.get_offset,
+            [core_mask(proc_core_id), 0, 0, 0, 0, 0],
+        )?;
+
+        Ok((response[0] & 0xFFFF) as u16 as i16 as i32)
+    }
+}
+
+/// Encodes the core into the SMU core mask, the ccd and the core within the ccd
+fn core_mask(proc_core_id: usize) -> u32 {
+    let ccd = (proc_core_id / CORES_PER_CCD) as u32;
+    let core = (proc_core_id % CORES_PER_CCD) as u32;
+
+    ((ccd << 8) | core) << 20
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    /// An in memory SMU, that stores the offsets per core mask
+    struct FakeSmu {
+        offsets: RefCell<HashMap<u32, u32>>,
+        ignore_writes: bool,
+    }
+
+    impl SmuTransport for FakeSmu {
+        fn send(
+            &self,
+            command: u32,
+            args: [u32; SMU_ARGS_COUNT],
+        ) -> Result<[u32; SMU_ARGS_COUNT], String> {
+            let mask = args[0] & 0xFFF0_0000;
+            let mut offsets = self.offsets.borrow_mut();
+
+            if command == ZEN3_COMMANDS.set_offset {
+                if !self.ignore_writes {
+                    offsets.insert(mask, args[0] & 0xFFFF);
+                }
+                Ok([0; SMU_ARGS_COUNT])
+            } else if command == ZEN3_COMMANDS.get_offset {
+                Ok([*offsets.get(&mask).unwrap_or(&0), 0, 0, 0, 0, 0])
+            } else {
+                Err(format!("Unknown command {:#x}", command))
+            }
+        }
+    }
+
+    fn fake_curve_optimizer(ignore_writes: bool) -> CurveOptimizer<FakeSmu> {
+        let transport = FakeSmu {
+            offsets: RefCell::new(HashMap::new()),
+            ignore_writes,
+        };
+        CurveOptimizer::new(transport, ZEN3_COMMANDS)
+    }
+
+    /// Creates a sysfs directory like the one of the ryzen_smu driver
+    /// The files are plain files, so reading rsmu_cmd returns the written command id
+    fn create_mock_sysfs(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(name);
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join(VERSION_FILE), "0.1.5\n").unwrap();
+        fs::write(root.join(RSMU_CMD_FILE), []).unwrap();
+        fs::write(root.join(SMU_ARGS_FILE), []).unwrap();
+        root
+    }
+
+    #[test]
+    fn get_commands_vermeer() {
+        // WHEN
+        let result = get_commands(25, 33);
+
+        // THEN
+        assert_eq!(result, Some(ZEN3_COMMANDS));
+    }
+
+    #[test]
+    fn get_commands_unsupported() {
+        // WHEN
+        let result = get_commands(6, 154);
+
+        // THEN
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn core_mask_second_ccd() {
+        // WHEN
+        let result = core_mask(9);
+
+        // THEN
+        assert_eq!(result, 0x1010_0000);
+    }
+
+    #[test]
+    fn set_offset_negative_read_back() {
+        // GIVEN
+        let curve_optimizer = fake_curve_optimizer(false);
+
+        // WHEN
+        let result = curve_optimizer.set_offset(8, -20);
+
+        // THEN
+        assert_eq!(result, Ok(()));
+        assert_eq!(curve_optimizer.get_offset(8), Ok(-20));
+        assert_eq!(curve_optimizer.get_offset(0), Ok(0));
+    }
+
+    #[test]
+    fn read_offsets_by_physical_core() {
+        // GIVEN
+        let curve_optimizer = fake_curve_optimizer(false);
+        curve_optimizer.set_offset(5, -12).unwrap();
+        curve_optimizer.set_offset(8, 3).unwrap();
+
+        // WHEN
+        let result = curve_optimizer.read_offsets(&[(0, 0), (5, 5), (6, 8)]);
+
+        // THEN
+        assert_eq!(result.unwrap(), HashMap::from([(0, 0), (5, -12), (6, 3)]));
+    }
+
+    #[test]
+    fn build_comparison_detects_differences() {
+        // GIVEN
+        let stored = HashMap::from([(0, -10), (1, -20)]);
+        let actual = HashMap::from([(0, -10), (1, -15), (2, -5)]);
+
+        // WHEN
+        let result = build_comparison(&stored, &actual);
+
+        // THEN
+        assert_eq!(
+            result,
+            vec![
+                OffsetComparison {
+                    core_id: 0,
+                    stored: -10,
+                    actual: -10,
+                    differs: false,
+                },
+                OffsetComparison {
+                    core_id: 1,
+                    stored: -20,
+                    actual: -15,
+                    differs: true,
+                },
+                OffsetComparison {
+                    core_id: 2,
+                    stored: 0,
+                    actual: -5,
+                    differs: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn set_offset_verification_failed() {
+        // GIVEN
+        let curve_optimizer = fake_curve_optimizer(true);
+
+        // WHEN
+        let result = curve_optimizer.set_offset(3, -15);
+
+        // THEN
+        assert!(result.unwrap_err().contains("verification failed"));
+    }
+
+    #[test]
+    fn set_offset_out_of_range() {
+        // GIVEN
+        let curve_optimizer = fake_curve_optimizer(false);
+
+        // WHEN
+        let result = curve_optimizer.set_offset(3, -31);
+
+        // THEN
+        assert!(result.unwrap_err().contains("out of range"));
+    }
+
+    #[test]
+    fn sysfs_transport_writes_arguments() {
+        // GIVEN
+        let root = create_mock_sysfs("pbo-assistant-sysfs-write-test");
+        let transport = SysfsTransport::new(&root).unwrap();
+
+        // WHEN
+        let _ = transport.send(0x0A, [0x0010_FFEC, 0, 0, 0, 0, 0]);
+
+        // THEN
+        let args = read_u32s(&root.join(SMU_ARGS_FILE)).unwrap();
+        let command = read_u32s(&root.join(RSMU_CMD_FILE)).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(args, vec![0x0010_FFEC, 0, 0, 0, 0, 0]);
+        assert_eq!(command, vec![0x0A]);
+    }
+
+    #[test]
+    fn sysfs_transport_rejected_command() {
+        // GIVEN
+        let root = create_mock_sysfs("pbo-assistant-sysfs-reject-test");
+        let transport = SysfsTransport::new(&root).unwrap();
+
+        // WHEN
+        let result = transport.send(0xFE, [0; SMU_ARGS_COUNT]);
+
+        // THEN
+        fs::remove_dir_all(&root).unwrap();
+        assert!(result.unwrap_err().contains("rejected"));
+    }
+
+    #[test]
+    fn sysfs_transport_missing_driver() {
+        // WHEN
+        let result = SysfsTransport::new(Path::new("/nonexistent/ryzen_smu_drv"));
+
+        // THEN
+        assert!(result.is_err());
+    }
+}
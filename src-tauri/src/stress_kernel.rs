@@ -0,0 +1,224 @@
+use crate::builtin::{self, BuiltinFailure, MethodControl, XorShift};
+use std::hint::black_box;
+
+/// A deterministic computation and its result on a stable CPU
+struct Workload {
+    name: &'static str,
+    compute: fn(u32) -> u64,
+    rounds: u32,
+    golden_checksum: u64,
+}
+
+/// Each workload takes about a millisecond, so stopping and pausing stays responsive
+const WORKLOADS: [Workload; 3] = [
+    Workload {
+        name: "integer",
+        compute: integer,
+        rounds: 20_000,
+        golden_checksum: 0x562F_BE00_7977_7D75,
+    },
+    Workload {
+        name: "floating point",
+        compute: floating_point,
+        rounds: 20_000,
+        golden_checksum: 0xF086_D5CA_A88C_70BE,
+    },
+    Workload {
+        name: "SIMD",
+        compute: simd,
+        rounds: 20_000,
+        golden_checksum: 0x597B_0FC4_5416_BDE2,
+    },
+];
+
+/// Start values of the SIMD lanes, eight 32 bit integers and four doubles fill one AVX2 register each
+const INTEGER_LANES: [u32; 8] = [
+    0x0123_4567,
+    0x89AB_CDEF,
+    0xFEDC_BA98,
+    0x7654_3210,
+    0x0F1E_2D3C,
+    0x4B5A_6978,
+    0x8796_A5B4,
+    0xC3D2_E1F0,
+];
+const FLOAT_LANES: [f64; 4] = [1.0, 2.5, 4.25, 8.125];
+const FLOAT_FACTOR: f64 = 1.000_001;
+
+/// Runs all workloads in turn until stopped and compares each result with its golden checksum
+pub fn run(physical_core_id: usize, control: &dyn MethodControl) -> Result<(), BuiltinFailure> {
+    let mut passes: u64 = 0;
+
+    while !control.should_stop() {
+        builtin::wait_while_paused(control);
+
+        for workload in &WORKLOADS {
+            let checksum = (workload.compute)(black_box(workload.rounds));
+            if checksum != workload.golden_checksum {
+                return Err(BuiltinFailure::Verification(format!(
+                    "{} workload returned checksum {:#x} instead of {:#x} after {} passes",
+                    workload.name, checksum, workload.golden_checksum, passes
+                )));
+            }
+        }
+        passes += 1;
+    }
+
+    println!(
+        "Stress kernel on core {} finished after {} passes",
+        physical_core_id, passes
+    );
+    Ok(())
+}
+
+/// Multiplications, divisions and bit operations on 64 bit integers
+fn integer(rounds: u32) -> u64 {
+    let mut random = XorShift::new(0x2545_F491_4F6C_DD1D);
+    let mut checksum: u64 = 0;
+
+    for _ in 0..rounds {
+        let value = random.next_u64();
+        let divisor = (value >> 32) | 1;
+        let high_product = ((value as u128 * checksum as u128) >> 64) as u64;
+
+        checksum = checksum.rotate_left(5)
+            ^ (value / divisor)
+            ^ (value % divisor).wrapping_mul(value)
+            ^ value.count_ones() as u64;
+        checksum = checksum.wrapping_add(high_product);
+    }
+
+    checksum
+}
+
+/// Fused multiply-adds, divisions and square roots on doubles
+fn floating_point(rounds: u32) -> u64 {
+    let mut random = XorShift::new(0x9E37_79B9_7F4A_7C15);
+    let mut root: f64 = 1.0;
+    let mut quotient: f64 = 0.5;
+
+    for _ in 0..rounds {
+        // Between 0 and 1 with the full 53 bit mantissa
+        let value = (random.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+
+        root = root.mul_add(0.999_999, value).sqrt();
+        quotient = (quotient + value) / (1.0 + root);
+    }
+
+    root.to_bits() ^ quotient.to_bits().rotate_left(32)
+}
+
+/// Shifts and adds on integer lanes and square roots on double lanes, with AVX2 if the CPU has it
+fn simd(rounds: u32) -> u64 {
+    #[cfg(target_arch = "x86_64")]
+    if is_x86_feature_detected!("avx2") {
+        // SAFETY: the CPU supports AVX2
+        return unsafe { simd_avx2(rounds) };
+    }
+
+    simd_scalar(rounds)
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn simd_avx2(rounds: u32) -> u64 {
+    use std::arch::x86_64::*;
+
+    let mut integers = _mm256_loadu_si256(INTEGER_LANES.as_ptr() as *const __m256i);
+    let mut floats = _mm256_loadu_pd(FLOAT_LANES.as_ptr());
+    let float_factor = _mm256_set1_pd(FLOAT_FACTOR);
+
+    for round in 0..rounds {
+        let shifted = _mm256_xor_si256(integers, _mm256_slli_epi32(integers, 7));
+        integers = _mm256_add_epi32(
+            _mm256_add_epi32(shifted, _mm256_srli_epi32(integers, 3)),
+            _mm256_set1_epi32(round as i32),
+        );
+
+        floats = _mm256_sqrt_pd(_mm256_add_pd(
+            _mm256_mul_pd(floats, float_factor),
+            _mm256_set1_pd(round as f64),
+        ));
+    }
+
+    let mut integer_lanes = [0u32; 8];
+    let mut float_lanes = [0f64; 4];
+    _mm256_storeu_si256(integer_lanes.as_mut_ptr() as *mut __m256i, integers);
+    _mm256_storeu_pd(float_lanes.as_mut_ptr(), floats);
+    fold_lanes(&integer_lanes, &float_lanes)
+}
+
+/// Same lane operations as the AVX2 variant, for CPUs without it
+fn simd_scalar(rounds: u32) -> u64 {
+    let mut integer_lanes = INTEGER_LANES;
+    let mut float_lanes = FLOAT_LANES;
+
+    for round in 0..rounds {
+        for lane in &mut integer_lanes {
+            *lane = (*lane ^ (*lane << 7))
+                .wrapping_add(*lane >> 3)
+                .wrapping_add(round);
+        }
+        for lane in &mut float_lanes {
+            *lane = (*lane * FLOAT_FACTOR + round as f64).sqrt();
+        }
+    }
+
+    fold_lanes(&integer_lanes, &float_lanes)
+}
+
+fn fold_lanes(integer_lanes: &[u32; 8], float_lanes: &[f64; 4]) -> u64 {
+    let integers = integer_lanes.iter().fold(0u64, |checksum, lane| {
+        checksum.rotate_left(11) ^ *lane as u64
+    });
+
+    float_lanes.iter().fold(integers, |checksum, lane| {
+        checksum.rotate_left(17) ^ lane.to_bits()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builtin::tests::CountingControl;
+    use std::cell::Cell;
+
+    #[test]
+    fn workloads_match_golden_checksums() {
+        // WHEN
+        let checksums: Vec<u64> = WORKLOADS
+            .iter()
+            .map(|workload| (workload.compute)(black_box(workload.rounds)))
+            .collect();
+
+        // THEN
+        let golden_checksums: Vec<u64> = WORKLOADS
+            .iter()
+            .map(|workload| workload.golden_checksum)
+            .collect();
+        assert_eq!(checksums, golden_checksums);
+    }
+
+    #[test]
+    fn simd_matches_scalar() {
+        // WHEN
+        let result = simd(black_box(1_000));
+
+        // THEN
+        assert_eq!(result, simd_scalar(1_000));
+    }
+
+    #[test]
+    fn run_until_stopped() {
+        // GIVEN
+        let control = CountingControl {
+            checks_left: Cell::new(2),
+        };
+
+        // WHEN
+        let result = run(0, &control);
+
+        // THEN
+        assert_eq!(result, Ok(()));
+    }
+}
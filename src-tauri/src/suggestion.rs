@@ -0,0 +1,191 @@
+use crate::cpu_test::CpuTestStatus;
+use crate::offset_model::OffsetModel;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// A suggested new offset for a core based on its last test result
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OffsetSuggestion {
+    pub core_id: usize,
+    pub current: i32,
+    pub suggested: i32,
+    pub passed: bool,
+}
+
+/// Suggests new offsets for the cores of the last run
+/// Failed cores are always included and step towards zero, passed cores only if requested
+/// Cores already at the limit of the offset range get no suggestion
+pub fn suggest_offsets(
+    test_status: &HashMap<usize, CpuTestStatus>,
+    offset_per_core: &HashMap<usize, i32>,
+    offset_model: OffsetModel,
+    include_passed: bool,
+) -> Vec<OffsetSuggestion> {
+    let mut suggestions: Vec<OffsetSuggestion> = test_status
+        .values()
+        .filter_map(|cpu_test_status| {
+            let passed = cpu_test_status.passed()?;
+            if passed && !include_passed {
+                return None;
+            }
+
+            let current = offset_per_core
+                .get(&cpu_test_status.core_id)
+                .copied()
+                .unwrap_or(0);
+            let suggested = if passed {
+                offset_model.clamp(current + offset_model.tightening_step())
+            } else {
+                offset_model.clamp(stabilized(current, offset_model))
+            };
+
+            (suggested != current).then_some(OffsetSuggestion {
+                core_id: cpu_test_status.core_id,
+                current,
+                suggested,
+                passed,
+            })
+        })
+        .collect();
+    suggestions.sort_by_key(|suggestion| suggestion.core_id);
+
+    suggestions
+}
+
+/// Steps the offset of a failed core towards zero without passing it
+/// A core that failed without an offset steps into the stabilizing direction of the model
+fn stabilized(current: i32, offset_model: OffsetModel) -> i32 {
+    let step = offset_model.stabilizing_step();
+    if current == 0 {
+        return step;
+    }
+
+    let towards_zero = current - current.signum() * step.abs();
+    if towards_zero.signum() == current.signum() {
+        towards_zero
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu_test::{CpuTestMethod, CpuTestMethodStatus, TestMethodResponse};
+
+    fn test_status(core_id: usize, state: CpuTestMethodStatus) -> (usize, CpuTestStatus) {
+        let method_response = TestMethodResponse {
+            method: CpuTestMethod::Prime95,
+            state,
+            current_secs: 60,
+            total_secs: 60,
+            error: None,
+            prime95_progress: None,
+            wake_latency: None,
+        };
+
+        let cpu_test_status = CpuTestStatus {
+            core_id,
+            max_clock: 5000,
+            verification_failed: false,
+            method_response: HashMap::from([(CpuTestMethod::Prime95, method_response)]),
+        };
+        (core_id, cpu_test_status)
+    }
+
+    #[test]
+    fn suggest_offsets_for_failed_cores() {
+        // GIVEN
+        let test_status = HashMap::from([
+            test_status(0, CpuTestMethodStatus::Failed),
+            test_status(1, CpuTestMethodStatus::Success),
+            test_status(2, CpuTestMethodStatus::Failed),
+        ]);
+        let offset_per_core = HashMap::from([(0, -22), (1, -20), (2, 29)]);
+
+        // WHEN
+        let result = suggest_offsets(
+            &test_status,
+            &offset_per_core,
+            OffsetModel::CurveOptimizer,
+            false,
+        );
+
+        // THEN
+        assert_eq!(
+            result,
+            vec![
+                OffsetSuggestion {
+                    core_id: 0,
+                    current: -22,
+                    suggested: -20,
+                    passed: false,
+                },
+                OffsetSuggestion {
+                    core_id: 2,
+                    current: 29,
+                    suggested: 27,
+                    passed: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn suggest_offsets_including_passed_cores() {
+        // GIVEN
+        let test_status = HashMap::from([
+            test_status(0, CpuTestMethodStatus::Success),
+            test_status(1, CpuTestMethodStatus::Success),
+            test_status(2, CpuTestMethodStatus::Error),
+        ]);
+        let offset_per_core = HashMap::from([(0, -15), (1, -30), (2, -10)]);
+
+        // WHEN
+        let result = suggest_offsets(
+            &test_status,
+            &offset_per_core,
+            OffsetModel::CurveOptimizer,
+            true,
+        );
+
+        // THEN
+        assert_eq!(
+            result,
+            vec![OffsetSuggestion {
+                core_id: 0,
+                current: -15,
+                suggested: -16,
+                passed: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn suggest_offsets_for_core_ratio() {
+        // GIVEN
+        let test_status = HashMap::from([test_status(0, CpuTestMethodStatus::Failed)]);
+        let offset_per_core = HashMap::from([(0, 2)]);
+
+        // WHEN
+        let result = suggest_offsets(
+            &test_status,
+            &offset_per_core,
+            OffsetModel::CoreRatio,
+            false,
+        );
+
+        // THEN
+        assert_eq!(result[0].suggested, 1);
+    }
+
+    #[test]
+    fn stabilized_stops_at_zero() {
+        // WHEN
+        let result =
+            [-1, 0, 1, -30].map(|current| stabilized(current, OffsetModel::CurveOptimizer));
+
+        // THEN
+        assert_eq!(result, [0, 2, 0, -28]);
+    }
+}
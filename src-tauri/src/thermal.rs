@@ -0,0 +1,355 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub const HWMON_PATH: &str = "/sys/class/hwmon";
+
+/// The cooldown is given up after this time, e.g. if the cooldown temperature is below idle
+pub const MAX_COOLDOWN_TIME: Duration = Duration::from_secs(10 * 60);
+
+/// hwmon drivers reporting the CPU package or die temperature, in order of preference
+const CPU_SENSOR_NAMES: [&str; 3] = ["k10temp", "zenpower", "coretemp"];
+
+/// Temperature limits of a test run in °C
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThermalConfig {
+    /// The current method is aborted above this temperature
+    pub max_temperature: u32,
+    /// The next method or core is started once the temperature dropped below this one
+    pub cooldown_temperature: u32,
+}
+
+impl Default for ThermalConfig {
+    fn default() -> Self {
+        ThermalConfig {
+            max_temperature: 95,
+            cooldown_temperature: 60,
+        }
+    }
+}
+
+impl ThermalConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.cooldown_temperature >= self.max_temperature {
+            return Err(format!(
+                "Cooldown temperature {} °C must be below the max temperature {} °C",
+                self.cooldown_temperature, self.max_temperature
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// A CPU temperature sensor of the hwmon sysfs interface
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemperatureSensor {
+    pub name: String,
+    inputs: Vec<PathBuf>,
+}
+
+impl TemperatureSensor {
+    /// Returns the hottest reading of the sensor in °C, e.g. Tctl or the hottest CCD
+    pub fn read(&self) -> Result<f64, String> {
+        let mut max_temperature: Option<f64> = None;
+
+        for input in &self.inputs {
+            let millidegrees = fs::read_to_string(input)
+                .map_err(|e| format!("Failed to read {}: {}", input.display(), e))?
+                .trim()
+                .parse::<i64>()
+                .map_err(|e| format!("Invalid temperature in {}: {}", input.display(), e))?;
+            let temperature = millidegrees as f64 / 1000.0;
+
+            max_temperature = Some(max_temperature.map_or(temperature, |max| max.max(temperature)));
+        }
+
+        max_temperature.ok_or_else(|| format!("Sensor {} has no temperature inputs", self.name))
+    }
+}
+
+/// The temperature limits of a run together with the sensor they are checked against
+#[derive(Debug, Clone)]
+pub struct ThermalGuard {
+    pub config: ThermalConfig,
+    pub sensor: Option<TemperatureSensor>,
+}
+
+impl ThermalGuard {
+    pub fn new(config: &ThermalConfig, hwmon_dir: &Path) -> ThermalGuard {
+        let sensor = find_cpu_sensor(hwmon_dir);
+        match &sensor {
+            Some(sensor) => println!("Using temperature sensor {}", sensor.name),
+            None => println!("No CPU temperature sensor found, temperatures are not checked"),
+        }
+
+        ThermalGuard {
+            config: config.clone(),
+            sensor,
+        }
+    }
+
+    /// Reads the current temperature, None if there is no sensor or it can not be read
+    pub fn temperature(&self) -> Option<f64> {
+        let sensor = self.sensor.as_ref()?;
+        sensor
+            .read()
+            .map_err(|e| println!("Failed to read the temperature: {}", e))
+            .ok()
+    }
+
+    /// Returns the temperature if it is above the max temperature
+    pub fn exceeded_temperature(&self) -> Option<f64> {
+        self.temperature()
+            .filter(|temperature| *temperature > self.config.max_temperature as f64)
+    }
+
+    /// Returns the temperature if it is not yet below the cooldown temperature
+    pub fn cooldown_pending(&self) -> Option<f64> {
+        self.temperature()
+            .filter(|temperature| *temperature >= self.config.cooldown_temperature as f64)
+    }
+}
+
+/// Finds the CPU temperature sensor in the given hwmon directory, None if there is none
+pub fn find_cpu_sensor(hwmon_dir: &Path) -> Option<TemperatureSensor> {
+    let mut sensors: Vec<TemperatureSensor> = fs::read_dir(hwmon_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            let name = fs::read_to_string(path.join("name"))
+                .ok()?
+                .trim()
+                .to_string();
+            if !CPU_SENSOR_NAMES.contains(&name.as_str()) {
+                return None;
+            }
+
+            let mut inputs: Vec<PathBuf> = fs::read_dir(&path)
+                .ok()?
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    let file_name = path.file_name().unwrap().to_string_lossy();
+                    file_name.starts_with("temp") && file_name.ends_with("_input")
+                })
+                .collect();
+            inputs.sort();
+
+            Some(TemperatureSensor {
+                name,
+                inputs: without_control_temperature(inputs),
+            })
+        })
+        .filter(|sensor| !sensor.inputs.is_empty())
+        .collect();
+
+    sensors.sort_by_key(|sensor| {
+        CPU_SENSOR_NAMES
+            .iter()
+            .position(|name| *name == sensor.name)
+    });
+    sensors.into_iter().next()
+}
+
+/// Drops the Tctl input if the die or CCD temperatures are reported as well
+/// Tctl is the control temperature, which some CPUs report with an offset above the real one
+fn without_control_temperature(inputs: Vec<PathBuf>) -> Vec<PathBuf> {
+    let labels: Vec<String> = inputs.iter().map(|input| input_label(input)).collect();
+    let has_real_temperature = labels
+        .iter()
+        .any(|label| label == "Tdie" || label.starts_with("Tccd"));
+    if !has_real_temperature {
+        return inputs;
+    }
+
+    inputs
+        .into_iter()
+        .zip(labels)
+        .filter(|(_, label)| label != "Tctl")
+        .map(|(input, _)| input)
+        .collect()
+}
+
+/// Returns the label of the input, e.g. "Tctl" for temp1_input, empty if it has none
+fn input_label(input: &Path) -> String {
+    let file_name = input.file_name().unwrap().to_string_lossy();
+    let label_path = input.with_file_name(file_name.replace("_input", "_label"));
+    fs::read_to_string(label_path)
+        .map(|label| label.trim().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a hwmon directory with one device per sensor name and its inputs in millidegrees
+    fn create_fake_hwmon(name: &str, sensors: &[(&str, &[&str])]) -> PathBuf {
+        let root = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&root);
+
+        for (index, (sensor_name, inputs)) in sensors.iter().enumerate() {
+            let device = root.join(format!("hwmon{}", index));
+            fs::create_dir_all(&device).unwrap();
+            fs::write(device.join("name"), format!("{}\n", sensor_name)).unwrap();
+            for (input_index, input) in inputs.iter().enumerate() {
+                let input_path = device.join(format!("temp{}_input", input_index + 1));
+                fs::write(input_path, format!("{}\n", input)).unwrap();
+            }
+        }
+        root
+    }
+
+    #[test]
+    fn find_cpu_sensor_k10temp() {
+        // GIVEN
+        let root = create_fake_hwmon(
+            "pbo-assistant-hwmon-k10temp-test",
+            &[
+                ("nvme", &["38850"]),
+                ("k10temp", &["72500", "68250", "81000"]),
+            ],
+        );
+
+        // WHEN
+        let sensor = find_cpu_sensor(&root).unwrap();
+        let temperature = sensor.read();
+
+        // THEN
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(sensor.name, "k10temp");
+        assert_eq!(temperature.unwrap(), 81.0);
+    }
+
+    #[test]
+    fn find_cpu_sensor_skips_tctl_with_ccd_temperatures() {
+        // GIVEN
+        let root = create_fake_hwmon(
+            "pbo-assistant-hwmon-tctl-test",
+            &[("k10temp", &["90000", "71000", "68000"])],
+        );
+        for (index, label) in ["Tctl", "Tccd1", "Tccd2"].iter().enumerate() {
+            fs::write(
+                root.join(format!("hwmon0/temp{}_label", index + 1)),
+                format!("{}\n", label),
+            )
+            .unwrap();
+        }
+
+        // WHEN
+        let sensor = find_cpu_sensor(&root).unwrap();
+        let temperature = sensor.read();
+
+        // THEN
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(temperature.unwrap(), 71.0);
+    }
+
+    #[test]
+    fn find_cpu_sensor_without_cpu_sensor() {
+        // GIVEN
+        let root = create_fake_hwmon(
+            "pbo-assistant-hwmon-no-cpu-test",
+            &[("nvme", &["38850"]), ("amdgpu", &["45000"])],
+        );
+
+        // WHEN
+        let result = find_cpu_sensor(&root);
+
+        // THEN
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn read_invalid_temperature() {
+        // GIVEN
+        let root = create_fake_hwmon(
+            "pbo-assistant-hwmon-invalid-test",
+            &[("coretemp", &["hot"])],
+        );
+        let sensor = find_cpu_sensor(&root).unwrap();
+
+        // WHEN
+        let result = sensor.read();
+
+        // THEN
+        fs::remove_dir_all(&root).unwrap();
+        assert!(result.unwrap_err().contains("Invalid temperature"));
+    }
+
+    #[test]
+    fn thermal_guard_limits() {
+        // GIVEN
+        let root = create_fake_hwmon("pbo-assistant-hwmon-guard-test", &[("k10temp", &["70000"])]);
+        let config = ThermalConfig {
+            max_temperature: 90,
+            cooldown_temperature: 60,
+        };
+        let guard = ThermalGuard::new(&config, &root);
+
+        // WHEN
+        let exceeded = guard.exceeded_temperature();
+        let cooldown_pending = guard.cooldown_pending();
+        fs::write(root.join("hwmon0/temp1_input"), "95000").unwrap();
+        let exceeded_when_hot = guard.exceeded_temperature();
+
+        // THEN
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(exceeded, None);
+        assert_eq!(cooldown_pending, Some(70.0));
+        assert_eq!(exceeded_when_hot, Some(95.0));
+    }
+
+    #[test]
+    fn thermal_guard_without_sensor() {
+        // GIVEN
+        let guard = ThermalGuard::new(
+            &ThermalConfig::default(),
+            Path::new("/nonexistent/pbo-assistant-hwmon"),
+        );
+
+        // WHEN
+        let result = (guard.exceeded_temperature(), guard.cooldown_pending());
+
+        // THEN
+        assert_eq!(result, (None, None));
+    }
+
+    #[test]
+    fn parse_config_missing_field() {
+        // GIVEN
+        let config_str = r#"{"max_temperature": 90}"#;
+
+        // WHEN
+        let result: Result<ThermalConfig, _> = serde_json::from_str(config_str);
+
+        // THEN
+        assert_eq!(
+            result.unwrap(),
+            ThermalConfig {
+                max_temperature: 90,
+                cooldown_temperature: 60,
+            }
+        );
+    }
+
+    #[test]
+    fn validate_cooldown_below_max() {
+        // GIVEN
+        let config = ThermalConfig {
+            max_temperature: 80,
+            cooldown_temperature: 85,
+        };
+
+        // WHEN
+        let result = config.validate();
+
+        // THEN
+        assert!(result.unwrap_err().contains("must be below"));
+    }
+}
@@ -1,82 +1,504 @@
-use crate::{cpu_info, process};
+use crate::binary::{BinaryInfo, BinarySource};
+use crate::cpu_test::CpuTestMethod;
+use crate::{binary, cpu_info, process};
 use flate2::read::GzDecoder;
-use std::io::{Cursor, Write};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::io::{Cursor, Read};
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
 use std::process::{Child, Command};
 use std::thread;
 use std::time::Duration;
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter, EnumString};
 use tar::Archive;
 
 pub const ERROR_MESSAGE: &str = "Errors encountered.";
-const PROCESS_PATH: &str = "/tmp/pbo-assistant/ycruncher/y-cruncher";
+const YCRUNCHER_ARCHIVE: &[u8] = include_bytes!("../../assets/ycruncher/ycruncher.tar.gz");
+const TESTS_PREFIX: &str = "Tests:";
+const STRESS_STARTED_PREFIX: &str = "Iteration:";
+const STARTUP_ERROR_MESSAGES: [&str; 3] = ["Invalid", "Unrecognized", "Unable to"];
+const MEMINFO_PATH: &str = "/proc/meminfo";
+const MEM_AVAILABLE_PREFIX: &str = "MemAvailable:";
 
-pub fn initialize() {
+lazy_static! {
+    static ref YCRUNCHER_ARCHIVE_SHA256: String = binary::sha256(YCRUNCHER_ARCHIVE);
+    /// The SHA-256 hashes of the files in the embedded archive
+    static ref EMBEDDED_FILE_HASHES: Result<Vec<(PathBuf, String)>, String> =
+        read_embedded_file_hashes();
+}
+
+/// The sub-tests of the y-cruncher component stress tester
+#[derive(
+    Debug, Clone, Copy, Eq, PartialEq, Hash, EnumIter, Display, EnumString, Serialize, Deserialize,
+)]
+#[strum(serialize_all = "UPPERCASE")]
+#[serde(rename_all = "UPPERCASE")]
+pub enum YCruncherTest {
+    /// Basecase + Karatsuba
+    Bkt,
+    /// BBP Digit Extraction
+    Bbp,
+    /// Small In-Cache FFT
+    Sft,
+    /// Small In-Cache N63
+    Snt,
+    /// Small In-Cache VT3
+    Svt,
+    /// Fast Fourier Transform
+    Fft,
+    /// Classic NTT (63-bit)
+    N63,
+    /// Vector Transform (3-Prime)
+    Vt3,
+}
+
+/// The y-cruncher stress test configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YCruncherConfig {
+    /// The sub-tests to run
+    pub tests: Vec<YCruncherTest>,
+    /// The memory to allocate in MiB, if none the y-cruncher default is used
+    /// The instances of the all-core stage share it
+    pub memory_mib: Option<u64>,
+    /// Path to an external y-cruncher binary, if none the embedded one is used
+    pub binary_path: Option<String>,
+}
+
+impl Default for YCruncherConfig {
+    fn default() -> Self {
+        YCruncherConfig {
+            tests: YCruncherTest::iter().collect(),
+            memory_mib: None,
+            binary_path: None,
+        }
+    }
+}
+
+/// Prepares y-cruncher for the test run and returns the binary to use
+pub fn initialize(config: &YCruncherConfig) -> BinaryInfo {
     // Kill all processes
     process::kill();
 
-    let memory_file = include_bytes!("../../assets/ycruncher/ycruncher.tar.gz");
+    extract();
+
+    let binary_info = resolve_binary(config);
+    print_binary_info(&binary_info);
 
-    std::fs::create_dir_all("/tmp/pbo-assistant/ycruncher").expect("Failed to create directory");
+    binary_info
+}
+
+/// The versioned directory the embedded archive is extracted to
+pub fn version_dir() -> PathBuf {
+    binary::version_dir("ycruncher", &YCRUNCHER_ARCHIVE_SHA256)
+}
 
-    let cursor = Cursor::new(memory_file);
+fn process_dir() -> PathBuf {
+    version_dir().join("ycruncher")
+}
+
+fn process_path() -> PathBuf {
+    process_dir().join("y-cruncher")
+}
+
+/// The working directory of the y-cruncher instance testing the core
+/// Instances running at the same time must not share their configuration or files
+fn instance_dir(core_id: usize) -> PathBuf {
+    version_dir().join(format!("core-{}", core_id))
+}
+
+/// Extracts the embedded y-cruncher binaries
+/// Reuses the files of a previous run if they are still intact
+fn extract() {
+    if verify_extracted().is_ok() {
+        return;
+    }
+
+    binary::create_private_dir(&process_dir());
+
+    let cursor = Cursor::new(YCRUNCHER_ARCHIVE);
     let gz = GzDecoder::new(cursor);
     let mut archive = Archive::new(gz);
 
-    let output_dir = "/tmp/pbo-assistant/";
-    archive.unpack(output_dir).expect("Failed to unpack");
+    // The directory modes stored in the archive must not widen the access to our directories
+    archive.set_preserve_permissions(false);
+    archive.unpack(version_dir()).expect("Failed to unpack");
+    binary::make_dirs_private(&version_dir()).expect("Failed to change permissions");
 
-    Command::new("chmod")
-        .arg("+x")
-        .arg(PROCESS_PATH)
-        .output()
+    std::fs::set_permissions(process_path(), std::fs::Permissions::from_mode(0o700))
         .expect("Failed to change permissions");
+
+    verify_extracted().expect("Failed to unpack");
+}
+
+/// Makes sure the extracted files match the files of the embedded archive
+fn verify_extracted() -> Result<(), String> {
+    let embedded_file_hashes = EMBEDDED_FILE_HASHES.as_ref().map_err(|e| e.clone())?;
+
+    for (path, sha256) in embedded_file_hashes {
+        binary::verify_file(&version_dir().join(path), sha256)?;
+    }
+
+    Ok(())
+}
+
+/// Reads the SHA-256 hashes of all files in the embedded archive
+fn read_embedded_file_hashes() -> Result<Vec<(PathBuf, String)>, String> {
+    let cursor = Cursor::new(YCRUNCHER_ARCHIVE);
+    let gz = GzDecoder::new(cursor);
+    let mut archive = Archive::new(gz);
+
+    let entries = archive
+        .entries()
+        .map_err(|e| format!("Failed to read the y-cruncher archive: {}", e))?;
+
+    let mut file_hashes = vec![];
+    for entry in entries {
+        let mut entry =
+            entry.map_err(|e| format!("Failed to read the y-cruncher archive: {}", e))?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let path = entry
+            .path()
+            .map_err(|e| format!("Failed to read the y-cruncher archive: {}", e))?
+            .into_owned();
+        let mut content = vec![];
+        entry
+            .read_to_end(&mut content)
+            .map_err(|e| format!("Failed to read the y-cruncher archive: {}", e))?;
+
+        file_hashes.push((path, binary::sha256(&content)));
+    }
+
+    Ok(file_hashes)
+}
+
+/// Resolves the y-cruncher binary to use, extracts the embedded binary if it is missing or modified
+pub fn resolve_binary(config: &YCruncherConfig) -> BinaryInfo {
+    binary::cached(
+        CpuTestMethod::YCruncher,
+        &config.binary_path,
+        |binary_info| match binary_info.source {
+            BinarySource::Embedded => verify_extracted(),
+            BinarySource::External => Ok(()),
+        },
+        || {
+            extract();
+
+            binary::resolve(
+                CpuTestMethod::YCruncher,
+                &config.binary_path,
+                &process_path(),
+                &["version"],
+            )
+        },
+    )
+}
+
+pub fn print_binary_info(binary_info: &BinaryInfo) {
+    if let Some(warning) = &binary_info.warning {
+        println!("Warning: {}", warning);
+    }
+    println!(
+        "Using {:?} y-cruncher {} ({})",
+        binary_info.source,
+        binary_info.version.as_deref().unwrap_or("unknown version"),
+        binary_info.path
+    );
+}
+
+/// Builds the y-cruncher stress test configuration file content
+/// The stress test runs forever, the time is managed by us
+fn build_stress_config(logical_core_id: usize, config: &YCruncherConfig) -> String {
+    let tests = config
+        .tests
+        .iter()
+        .map(|test| format!("            \"{}\"", test))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let total_memory = match config.memory_mib {
+        Some(memory_mib) => format!("        TotalMemory : {}\n", memory_mib * 1024 * 1024),
+        None => "".to_string(),
+    };
+
+    format!(
+        "{{\n    Action : \"StressTest\"\n    StressTest : {{\n        AllocateLocally : \"true\"\n        LogicalCores : [{}]\n{}        SecondsTotal : 0\n        StopOnError : \"true\"\n        Tests : [\n{}\n        ]\n    }}\n}}\n",
+        logical_core_id, total_memory, tests
+    )
+}
+
+/// Returns the configuration of each of several instances running at the same time
+/// They share the memory, without a configured memory half of the available memory is used
+pub fn config_per_instance(config: &YCruncherConfig, instance_count: usize) -> YCruncherConfig {
+    if instance_count <= 1 {
+        return config.clone();
+    }
+
+    let total_memory_mib = config
+        .memory_mib
+        .or_else(|| available_memory_mib().map(|memory_mib| memory_mib / 2));
+    YCruncherConfig {
+        memory_mib: total_memory_mib.map(|memory_mib| (memory_mib / instance_count as u64).max(1)),
+        ..config.clone()
+    }
+}
+
+/// Returns the memory available for starting new processes in MiB
+fn available_memory_mib() -> Option<u64> {
+    let meminfo = std::fs::read_to_string(MEMINFO_PATH).ok()?;
+    parse_available_memory_mib(&meminfo)
+}
+
+fn parse_available_memory_mib(meminfo: &str) -> Option<u64> {
+    // e.g. "MemAvailable:   28123456 kB"
+    let available_kib = meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix(MEM_AVAILABLE_PREFIX))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+
+    Some(available_kib / 1024)
 }
 
-fn spawn_process(physical_core_id: usize) -> Child {
+fn spawn_process(
+    physical_core_id: usize,
+    binary_path: &str,
+    config: &YCruncherConfig,
+) -> Result<Child, String> {
     let logical_core_id = cpu_info::get_first_logical_core_id_for(physical_core_id);
 
-    let mut child_process = Command::new(PROCESS_PATH)
-        .stdin(std::process::Stdio::piped())
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
-        .spawn()
-        .expect("Failed to start the process");
-
-    let mut stdin = child_process.stdin.take().unwrap();
-
-    // Apply this inputs:
-    // 1   Component Stress Tester
-    // 1   Modify Cores
-    // d   Disable all Cores
-    // #   Number of logical core id
-    // \n  confirm core dialog
-    // 2   Modify memory settings
-    // \n  confirm core dialog
-    // 5   Run Forever (we are managing the time)
-    // 0   Start Stress test
-    stdin.write_all(b"1\n").unwrap();
-    stdin.write_all(b"1\n").unwrap();
-    stdin.write_all(b"d\n").unwrap();
-    stdin
-        .write_all(format!("{}\n", logical_core_id).as_bytes())
-        .unwrap();
-    stdin.write_all(b"\n").unwrap();
-    stdin.write_all(b"2\n").unwrap();
-    stdin.write_all(b"\n").unwrap();
-    stdin.write_all(b"5\n").unwrap();
-    stdin.write_all(b"0\n").unwrap();
+    let working_dir = instance_dir(physical_core_id);
+    binary::create_private_dir(&working_dir);
+    let config_path = working_dir.join("stress.cfg");
+    std::fs::write(&config_path, build_stress_config(logical_core_id, config))
+        .map_err(|e| format!("Failed to write {}: {}", config_path.display(), e))?;
+
+    // Run y-cruncher non-interactive with the stress test configuration
+    let child_process = process::spawn_tracked(
+        Command::new(binary_path)
+            .arg("config")
+            .arg(&config_path)
+            .current_dir(&working_dir)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped()),
+    )
+    .map_err(|e| format!("Failed to start {}: {}", binary_path, e))?;
 
     let proccess_id = child_process.id();
 
     println!("Started process with id: {}", proccess_id);
 
-    child_process
+    Ok(child_process)
 }
 
-pub fn start_verification(core_id: usize) -> Child {
-    let child = spawn_process(core_id);
+/// Starts the y-cruncher stress test on the given core
+/// Refuses to start the embedded binary if it was modified since the extraction
+pub fn start_verification(
+    core_id: usize,
+    binary_info: &BinaryInfo,
+    config: &YCruncherConfig,
+) -> Result<Child, String> {
+    if binary_info.source == BinarySource::Embedded {
+        verify_extracted()?;
+    }
+
+    let child = spawn_process(core_id, &binary_info.path, config)?;
 
     // Wait a second to make sure the process is started
     thread::sleep(Duration::from_secs(1));
 
-    child
+    Ok(child)
+}
+
+/// Reads the y-cruncher output until the stress test is running
+/// Fails if y-cruncher reports a problem, runs other tests than configured
+/// or exits before the stress test started
+pub fn wait_for_stress_start(
+    lines: &mut impl Iterator<Item = String>,
+    config: &YCruncherConfig,
+) -> Result<(), String> {
+    let mut tests_confirmed = false;
+
+    for line in lines {
+        let line = line.trim();
+
+        if STARTUP_ERROR_MESSAGES
+            .iter()
+            .any(|message| line.contains(message))
+        {
+            return Err(format!("y-cruncher rejected the configuration: {}", line));
+        }
+
+        if let Some(tests) = line.strip_prefix(TESTS_PREFIX) {
+            let running_tests: Vec<&str> = tests.split_whitespace().collect();
+            let expected_tests: Vec<String> =
+                config.tests.iter().map(|test| test.to_string()).collect();
+
+            if running_tests != expected_tests {
+                return Err(format!(
+                    "y-cruncher runs unexpected tests: {} (expected: {})",
+                    running_tests.join(" "),
+                    expected_tests.join(" ")
+                ));
+            }
+
+            tests_confirmed = true;
+        }
+
+        if line.starts_with(STRESS_STARTED_PREFIX) {
+            return if tests_confirmed {
+                Ok(())
+            } else {
+                Err("y-cruncher started without confirming the tests to run".to_string())
+            };
+        }
+    }
+
+    Err("y-cruncher exited before the stress test started".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STRESS_STARTED: &str = include_str!("../test_ycruncher_output/stress_started");
+    const INVALID_CONFIG: &str = include_str!("../test_ycruncher_output/invalid_config");
+    const UNEXPECTED_TESTS: &str = include_str!("../test_ycruncher_output/unexpected_tests");
+
+    fn zen_config() -> YCruncherConfig {
+        YCruncherConfig {
+            tests: vec![
+                YCruncherTest::Bkt,
+                YCruncherTest::Bbp,
+                YCruncherTest::Sft,
+                YCruncherTest::Fft,
+                YCruncherTest::N63,
+                YCruncherTest::Vt3,
+            ],
+            memory_mib: None,
+            binary_path: None,
+        }
+    }
+
+    #[test]
+    fn build_stress_config_default_memory() {
+        // GIVEN
+        let config = YCruncherConfig {
+            tests: vec![YCruncherTest::Bkt, YCruncherTest::Fft],
+            memory_mib: None,
+            binary_path: None,
+        };
+
+        // WHEN
+        let result = build_stress_config(4, &config);
+
+        // THEN
+        assert!(result.contains("LogicalCores : [4]"));
+        assert!(result.contains("            \"BKT\"\n            \"FFT\"\n"));
+        assert!(!result.contains("TotalMemory"));
+    }
+
+    #[test]
+    fn build_stress_config_custom_memory() {
+        // GIVEN
+        let config = YCruncherConfig {
+            tests: vec![YCruncherTest::N63, YCruncherTest::Vt3],
+            memory_mib: Some(2048),
+            binary_path: None,
+        };
+
+        // WHEN
+        let result = build_stress_config(0, &config);
+
+        // THEN
+        assert!(result.contains("TotalMemory : 2147483648"));
+        assert!(result.contains("            \"N63\"\n            \"VT3\"\n"));
+    }
+
+    #[test]
+    fn config_per_instance_splits_memory() {
+        // GIVEN
+        let config = YCruncherConfig {
+            memory_mib: Some(8192),
+            ..zen_config()
+        };
+
+        // WHEN
+        let single = config_per_instance(&config, 1);
+        let shared = config_per_instance(&config, 6);
+
+        // THEN
+        assert_eq!(single.memory_mib, Some(8192));
+        assert_eq!(shared.memory_mib, Some(1365));
+        assert_eq!(shared.tests, config.tests);
+    }
+
+    #[test]
+    fn parse_available_memory() {
+        // GIVEN
+        let meminfo = "MemTotal:       32768000 kB\nMemFree:         1024000 kB\nMemAvailable:   20480000 kB\n";
+
+        // WHEN
+        let result = parse_available_memory_mib(meminfo);
+
+        // THEN
+        assert_eq!(result, Some(20000));
+    }
+
+    #[test]
+    fn wait_for_stress_start_started() {
+        // GIVEN
+        let mut lines = STRESS_STARTED.lines().map(String::from);
+
+        // WHEN
+        let result = wait_for_stress_start(&mut lines, &zen_config());
+
+        // THEN
+        assert_eq!(result, Ok(()));
+        assert!(lines.next().unwrap().contains("Running BKT"));
+    }
+
+    #[test]
+    fn wait_for_stress_start_invalid_config() {
+        // GIVEN
+        let mut lines = INVALID_CONFIG.lines().map(String::from);
+
+        // WHEN
+        let result = wait_for_stress_start(&mut lines, &zen_config());
+
+        // THEN
+        assert!(result.unwrap_err().contains("rejected the configuration"));
+    }
+
+    #[test]
+    fn wait_for_stress_start_unexpected_tests() {
+        // GIVEN
+        let mut lines = UNEXPECTED_TESTS.lines().map(String::from);
+
+        // WHEN
+        let result = wait_for_stress_start(&mut lines, &zen_config());
+
+        // THEN
+        assert!(result.unwrap_err().contains("unexpected tests"));
+    }
+
+    #[test]
+    fn wait_for_stress_start_exited() {
+        // GIVEN
+        let mut lines = STRESS_STARTED.lines().take(5).map(String::from);
+
+        // WHEN
+        let result = wait_for_stress_start(&mut lines, &zen_config());
+
+        // THEN
+        assert!(result.unwrap_err().contains("exited"));
+    }
 }
@@ -1,31 +1,136 @@
 const {invoke} = window.__TAURI__.core;
-const {message} = window.__TAURI__.dialog;
+const {message, open, save} = window.__TAURI__.dialog;
+const {listen} = window.__TAURI__.event;
 
 const durationPerCoreInput = document.getElementById("durationPerCoreInput");
 const coresToTestInput = document.getElementById("coresToTestInput");
 const testMethodsLayout = document.getElementById("testMethodsLayout");
 const startButton = document.getElementById("startButton");
+const cleanupButton = document.getElementById("cleanupButton");
+const applyOffsetsCheckbox = document.getElementById("applyOffsetsCheckbox");
+const labelApplyOffsets = document.getElementById("labelApplyOffsets");
+const readOffsetsButton = document.getElementById("readOffsetsButton");
 const labelCoresToTest = document.getElementById("labelCoresToTest");
+const ycruncherTestsLayout = document.getElementById("ycruncherTestsLayout");
+const ycruncherMemoryInput = document.getElementById("ycruncherMemoryInput");
+const maxTemperatureInput = document.getElementById("maxTemperatureInput");
+const cooldownTemperatureInput = document.getElementById("cooldownTemperatureInput");
+const allCoreCheckbox = document.getElementById("allCoreCheckbox");
+const allCoreDurationInput = document.getElementById("allCoreDurationInput");
+const loadSweepCheckbox = document.getElementById("loadSweepCheckbox");
+const loadSweepDurationInput = document.getElementById("loadSweepDurationInput");
+const offsetModelSelect = document.getElementById("offsetModelSelect");
+const labelOffsetModel = document.getElementById("labelOffsetModel");
+const profileSelect = document.getElementById("profileSelect");
+const profileNameInput = document.getElementById("profileNameInput");
+const biosVersionInput = document.getElementById("biosVersionInput");
+const pboLimitsInput = document.getElementById("pboLimitsInput");
+const notesInput = document.getElementById("notesInput");
+const saveProfileButton = document.getElementById("saveProfileButton");
+const deleteProfileButton = document.getElementById("deleteProfileButton");
+const importProfileButton = document.getElementById("importProfileButton");
+const exportProfileButton = document.getElementById("exportProfileButton");
+const PROFILE_FILE_FILTERS = [{name: "Profile", extensions: ["json", "csv"]}];
 
-let timer;
 let isTestRunning = false;
+let isTestPaused = false;
 let physicalCoresCount = 0;
 let appConfig = {};
+let testStatusPerCore = {};
+// Ids of the cards of cores tested together by their name, the all-core stage and each set of the load sweep
+let coreSetIds = {};
+let currentCoreSetId = null;
+let ledgerPerCore = {};
+let offsetModel = {unit: "counts", min: -30, max: 30, step: 1, runtime_apply: true};
+let isSmuAvailable = false;
 
 window.addEventListener("DOMContentLoaded", () => {
     loadConfig().then(() => {
         loadTestMethods();
+        loadYCruncherTests();
+        loadSmuStatus();
+        loadOffsetModel();
         loadCores();
+        loadProfiles();
+        loadLedger();
+        loadRunState();
     });
 
     startButton.addEventListener("click", () => onStartTestButtonClick());
+    cleanupButton.addEventListener("click", () => onCleanupButtonClick());
+    readOffsetsButton.addEventListener("click", () => showSmuOffsets());
+    applyOffsetsCheckbox.addEventListener("change", () => {
+        appConfig.apply_offsets_via_smu = applyOffsetsCheckbox.checked;
+        invoke("set_apply_offsets_via_smu", {enabled: applyOffsetsCheckbox.checked});
+    });
+    offsetModelSelect.addEventListener("change", () => onOffsetModelSelected());
+    profileSelect.addEventListener("change", () => onProfileSelected());
+    saveProfileButton.addEventListener("click", () => onSaveProfileButtonClick());
+    deleteProfileButton.addEventListener("click", () => onDeleteProfileButtonClick());
+    importProfileButton.addEventListener("click", () => onImportProfileButtonClick());
+    exportProfileButton.addEventListener("click", () => onExportProfileButtonClick());
+
+    // The config file was edited outside the app
+    listen("config-changed", (event) => onConfigChanged(event.payload));
+    listen("config-error", (event) => {
+        message(event.payload, {title: 'Config not reloaded', kind: 'warning'});
+    });
+
+    // Status changes of the running test
+    listen("core-started", (event) => {
+        clearCooldownStatus();
+        updateCpuStatus(event.payload);
+    });
+    listen("method-progress", (event) => onMethodProgress(event.payload));
+    listen("clock-update", (event) => onClockUpdate(event.payload));
+    listen("test-failure", (event) => onTestFailure(event.payload));
+    listen("cooldown", (event) => onCooldown(event.payload));
+    listen("all-core-progress", (event) => updateAllCoreStatus(event.payload));
+    listen("run-finished", (event) => onRunFinished(event.payload));
 });
 
 function loadConfig() {
-    return invoke("get_config").then((config) => {
-        appConfig = JSON.parse(config);
-        durationPerCoreInput.value = appConfig.test_duration_per_core;
-        coresToTestInput.value = appConfig.cores_to_test;
+    return invoke("get_config").then((loadedConfig) => {
+        appConfig = loadedConfig.config;
+
+        // The config could not be read and was reset
+        if (loadedConfig.warning) {
+            message(loadedConfig.warning, {title: 'Config reset', kind: 'warning'});
+        }
+
+        showConfigInputs();
+    });
+}
+
+function showConfigInputs() {
+    durationPerCoreInput.value = appConfig.test_duration_per_core;
+    coresToTestInput.value = appConfig.cores_to_test;
+    ycruncherMemoryInput.value = appConfig.ycruncher.memory_mib ?? "";
+    maxTemperatureInput.value = appConfig.thermal.max_temperature;
+    cooldownTemperatureInput.value = appConfig.thermal.cooldown_temperature;
+    allCoreCheckbox.checked = appConfig.all_core.enabled;
+    allCoreDurationInput.value = appConfig.all_core.duration;
+    loadSweepCheckbox.checked = appConfig.load_sweep.enabled;
+    loadSweepDurationInput.value = appConfig.load_sweep.duration_per_step;
+    applyOffsetsCheckbox.checked = appConfig.apply_offsets_via_smu;
+}
+
+// Shows the reloaded config, the inputs are kept while a test is running
+function onConfigChanged(config) {
+    appConfig = config;
+    loadProfiles();
+    loadOffsetModel();
+    showOffsets();
+    if (isTestRunning) {
+        return;
+    }
+
+    showConfigInputs();
+    testMethodsLayout.querySelectorAll("input[type=checkbox]").forEach((checkbox) => {
+        checkbox.checked = appConfig.active_test_methods.includes(checkbox.value);
+    });
+    ycruncherTestsLayout.querySelectorAll("input[type=checkbox]").forEach((checkbox) => {
+        checkbox.checked = appConfig.ycruncher.tests.includes(checkbox.value);
     });
 }
 
@@ -33,11 +138,213 @@ function loadConfig() {
 // Show them in the label: labelCoresToTest
 function loadCores() {
     invoke("get_physical_cores").then((cores) => {
-        physicalCoresCount = JSON.parse(cores) - 1;
+        physicalCoresCount = cores - 1;
         labelCoresToTest.innerText = `Physical cores to test (0 - ${physicalCoresCount})`;
     });
 }
 
+// Checks if offsets can be applied at runtime through the ryzen_smu driver
+function loadSmuStatus() {
+    invoke("get_smu_status").then((smuStatus) => {
+        isSmuAvailable = smuStatus.available;
+        updateApplyOffsetsState();
+        labelApplyOffsets.title = smuStatus.available
+            ? `Apply Curve Optimizer offsets at runtime via ryzen_smu (${smuStatus.family_name})`
+            : smuStatus.error;
+        if (!smuStatus.available) {
+            applyOffsetsCheckbox.checked = false;
+        }
+    });
+}
+
+// Offsets can only be applied at runtime via ryzen_smu for the Curve Optimizer
+function updateApplyOffsetsState() {
+    const canApply = isSmuAvailable && offsetModel.runtime_apply;
+    applyOffsetsCheckbox.disabled = !canApply;
+    readOffsetsButton.disabled = !canApply;
+}
+
+// Loads the offset model, which defines unit and range of the offsets
+function loadOffsetModel() {
+    invoke("get_offset_model").then(onOffsetModelChanged);
+}
+
+function onOffsetModelSelected() {
+    // The offsets and the ledger records of the previous model no longer apply
+    invoke("set_offset_model", {model: offsetModelSelect.value || null})
+        .then(() => invoke("get_config"))
+        .then((loadedConfig) => {
+            onConfigChanged(loadedConfig.config);
+            loadLedger();
+        })
+        .catch(showError);
+}
+
+function onOffsetModelChanged(offsetModelSettings) {
+    offsetModel = offsetModelSettings.active;
+
+    offsetModelSelect.innerHTML = "";
+    const autoOption = document.createElement("option");
+    autoOption.value = "";
+    autoOption.innerText = `Auto (${offsetModel.model})`;
+    offsetModelSelect.appendChild(autoOption);
+    offsetModelSettings.models.forEach((model) => {
+        const option = document.createElement("option");
+        option.value = model;
+        option.innerText = model;
+        offsetModelSelect.appendChild(option);
+    });
+    offsetModelSelect.value = offsetModelSettings.configured ?? "";
+    labelOffsetModel.title = `${offsetModel.min} to ${offsetModel.max} ${offsetModel.unit}`;
+
+    // Update unit and range of the already visible cores
+    document.querySelectorAll(".offsetInput").forEach((offsetInput) => {
+        offsetInput.min = offsetModel.min;
+        offsetInput.max = offsetModel.max;
+        offsetInput.step = offsetModel.step;
+    });
+    document.querySelectorAll(".offsetLabel").forEach((offsetLabel) => {
+        offsetLabel.innerText = `Offset (${offsetModel.unit})`;
+    });
+
+    updateApplyOffsetsState();
+}
+
+// Shows the offsets currently set in the SMU compared to the stored offsets
+function showSmuOffsets() {
+    invoke("get_smu_offsets").then((comparison) => {
+        const table = document.createElement("table");
+        table.className = "offsetComparison";
+        const header = table.insertRow();
+        ["Core", "Config", "SMU"].forEach((title) => {
+            const cell = document.createElement("th");
+            cell.innerText = title;
+            header.appendChild(cell);
+        });
+
+        comparison.forEach((entry) => {
+            const row = table.insertRow();
+            row.insertCell().innerText = entry.core_id;
+            row.insertCell().innerText = entry.stored;
+            row.insertCell().innerText = entry.actual;
+            if (entry.differs) {
+                row.className = "offsetDiffers";
+            }
+        });
+
+        const differentCores = comparison.filter((entry) => entry.differs).length;
+        clearSummaryLayout(differentCores > 0
+            ? `${differentCores} core(s) differ from the stored offsets`
+            : "All offsets match the stored offsets");
+        document.getElementById("summaryLayout").appendChild(table);
+    }).catch(showError);
+}
+
+// Fills the profile selection and the metadata of the active profile
+function loadProfiles() {
+    profileSelect.innerHTML = "";
+
+    const noProfileOption = document.createElement("option");
+    noProfileOption.value = "";
+    noProfileOption.innerText = "No profile";
+    profileSelect.appendChild(noProfileOption);
+
+    appConfig.profiles.forEach((profile) => {
+        const option = document.createElement("option");
+        option.value = profile.name;
+        option.innerText = profile.name;
+        profileSelect.appendChild(option);
+    });
+
+    const activeProfile = appConfig.profiles.find((profile) => profile.name === appConfig.active_profile);
+    profileSelect.value = activeProfile ? activeProfile.name : "";
+    profileNameInput.value = activeProfile?.name ?? "";
+    biosVersionInput.value = activeProfile?.bios_version ?? "";
+    pboLimitsInput.value = activeProfile?.pbo_limits ?? "";
+    notesInput.value = activeProfile?.notes ?? "";
+    deleteProfileButton.disabled = !activeProfile;
+    exportProfileButton.disabled = !activeProfile;
+}
+
+// Takes over the profiles and offsets of the config returned by a profile command
+function onProfilesChanged(updatedConfig) {
+    appConfig.profiles = updatedConfig.profiles;
+    appConfig.active_profile = updatedConfig.active_profile;
+    appConfig.offset_per_core = updatedConfig.offset_per_core;
+
+    loadProfiles();
+    showOffsets();
+}
+
+// Shows the configured offsets on the already visible cores
+function showOffsets() {
+    document.querySelectorAll(".cpuLayout").forEach((cpuLayout) => {
+        const coreId = cpuLayout.id.replace("cpu", "");
+        const offsetInput = document.getElementById(`offset${coreId}`);
+        if (offsetInput) {
+            offsetInput.value = appConfig.offset_per_core[coreId] ?? 0;
+        }
+    });
+}
+
+// Shows the error of a command, which has a code and a message
+async function showError(error) {
+    await message(error.message, {title: 'Error', kind: 'error'});
+}
+
+function onProfileSelected() {
+    if (profileSelect.value === "") {
+        profileSelect.value = appConfig.active_profile ?? "";
+        return;
+    }
+
+    invoke("switch_profile", {name: profileSelect.value})
+        .then(onProfilesChanged)
+        .catch(async (error) => {
+            profileSelect.value = appConfig.active_profile ?? "";
+            await showError(error);
+        });
+}
+
+function onSaveProfileButtonClick() {
+    invoke("save_profile", {
+        name: profileNameInput.value.trim(),
+        biosVersion: biosVersionInput.value,
+        pboLimits: pboLimitsInput.value,
+        notes: notesInput.value,
+    }).then(onProfilesChanged).catch(showError);
+}
+
+function onDeleteProfileButtonClick() {
+    invoke("delete_profile", {name: appConfig.active_profile})
+        .then(onProfilesChanged)
+        .catch(showError);
+}
+
+async function onImportProfileButtonClick() {
+    const path = await open({filters: PROFILE_FILE_FILTERS});
+    if (!path) {
+        return;
+    }
+
+    invoke("import_profile", {path: path.path ?? path})
+        .then(onProfilesChanged)
+        .catch(showError);
+}
+
+async function onExportProfileButtonClick() {
+    const path = await save({
+        defaultPath: `${appConfig.active_profile}.json`,
+        filters: PROFILE_FILE_FILTERS,
+    });
+    if (!path) {
+        return;
+    }
+
+    invoke("export_profile", {name: appConfig.active_profile, path: path})
+        .catch(showError);
+}
+
 function clearSummaryLayout(innerHTML = "") {
     const summaryLayout = document.getElementById("summaryLayout");
     summaryLayout.innerHTML = innerHTML;
@@ -53,6 +360,16 @@ function onStartTestButtonClick() {
     }
 }
 
+// Removes extracted binaries of other versions
+function onCleanupButtonClick() {
+    invoke("cleanup_binaries").then(async (removedDirs) => {
+        const text = removedDirs.length > 0
+            ? `Removed: ${removedDirs.join(", ")}`
+            : "No stale binaries found";
+        await message(text, {title: 'Clean cache', kind: 'info'});
+    }).catch(showError);
+}
+
 function startTest() {
     const testMethods = [];
     document.querySelectorAll('.testMethod input[type=checkbox]')
@@ -61,47 +378,115 @@ function startTest() {
                 testMethods.push(checkbox.value);
             }
         });
+    const ycruncherTests = [];
+    document.querySelectorAll('.ycruncherTest input[type=checkbox]')
+        .forEach((checkbox) => {
+            if (checkbox.checked) {
+                ycruncherTests.push(checkbox.value);
+            }
+        });
+    const ycruncherMemory = parseInt(ycruncherMemoryInput.value);
     const durationPerCore = durationPerCoreInput.value;
     const coresToTest = coresToTestInput.value;
 
     // Clear cpusLayout
     const cpusLayout = document.getElementById("cpusLayout");
     cpusLayout.innerHTML = "";
+    testStatusPerCore = {};
+    coreSetIds = {};
+    currentCoreSetId = null;
 
     // Build app config
     appConfig.test_duration_per_core = durationPerCore;
     appConfig.cores_to_test = coresToTest;
     appConfig.active_test_methods = testMethods;
+    appConfig.apply_offsets_via_smu = applyOffsetsCheckbox.checked;
+    appConfig.ycruncher = {
+        ...appConfig.ycruncher,
+        tests: ycruncherTests,
+        memory_mib: isNaN(ycruncherMemory) ? null : ycruncherMemory,
+    };
+    appConfig.thermal = {
+        max_temperature: parseInt(maxTemperatureInput.value),
+        cooldown_temperature: parseInt(cooldownTemperatureInput.value),
+    };
+    appConfig.all_core = {
+        enabled: allCoreCheckbox.checked,
+        duration: allCoreDurationInput.value,
+    };
+    appConfig.load_sweep = {
+        enabled: loadSweepCheckbox.checked,
+        duration_per_step: loadSweepDurationInput.value,
+    };
 
-    // Start the actual test
-    invoke("start_test", {
-        testMethods: testMethods,
-        durationPerCore: durationPerCore,
-        coresToTest: coresToTest,
-        appConfig: JSON.stringify(appConfig),
-    }).then((_) => {
-        isTestRunning = true;
-        startButton.innerText = "Stop";
-        startStatusPolling();
-    }).catch(async (errorMsg) => {
-        await message(errorMsg, {title: 'Error', kind: 'error'});
+    // Start the actual test, it can already be stopped while preparing
+    isTestRunning = true;
+    startButton.innerText = "Stop";
+    invoke("start_test", {config: appConfig}).then(() => {
+        loadTestStatus();
+    }).catch((error) => {
+        stopTest();
+        showError(error);
     });
 }
 
 function onStopPressed() {
     invoke("stop_test").then(() => {
         stopTest();
+    }).catch(async (error) => {
+        // The run finished in the meantime
+        if (error.code === "not_running") {
+            stopTest();
+            return;
+        }
+        await showError(error);
+    });
+}
+
+// Shows a run that is still active, e.g. after the window was reloaded
+function loadRunState() {
+    invoke("get_run_state").then((runState) => {
+        if (runState === "Running" || runState === "Paused" || runState === "Stopping") {
+            isTestRunning = runState !== "Stopping";
+            isTestPaused = runState === "Paused";
+            startButton.innerText = isTestRunning ? "Stop" : "Start";
+            loadTestStatus();
+        }
     });
 }
 
 function stopTest() {
-    clearInterval(timer);
     isTestRunning = false;
+    isTestPaused = false;
     startButton.innerText = "Start";
-    updateTestStatus();
+    updateTestControls();
+}
+
+function onPauseButtonClick() {
+    invoke(isTestPaused ? "resume_test" : "pause_test").then(() => {
+        isTestPaused = !isTestPaused;
+        updateTestControls();
+    }).catch(showError);
+}
+
+// Skips the current "Method" or all remaining methods of the current "Core"
+function onSkipButtonClick(target) {
+    invoke("skip_test", {target: target}).catch(showError);
+}
+
+// Shows whether the run is paused on the controls of the core cards
+function updateTestControls() {
+    document.querySelectorAll(".pauseButton").forEach((button) => {
+        button.innerText = isTestPaused ? "Resume" : "Pause";
+    });
+    document.querySelectorAll(".skipButton").forEach((button) => {
+        button.disabled = isTestPaused;
+    });
 }
 
 function updateCpuStatus(cpuTestStatus) {
+    testStatusPerCore[cpuTestStatus.core_id] = cpuTestStatus;
+
     // Find div layout for the current core
     let cpuLayout = document.getElementById(`cpu${cpuTestStatus.core_id}`);
 
@@ -114,21 +499,38 @@ function updateCpuStatus(cpuTestStatus) {
     }
 }
 
-// Updates the offset of the core by the given delta
-function addValueToOffset(coreId, toAdd) {
+// Updates the offset of the core by the given number of steps of the offset model
+function addValueToOffset(coreId, steps) {
     const offsetInput = document.getElementById(`offset${coreId}`);
-    let nextValue = parseInt(offsetInput.value) + toAdd;
+    let nextValue = parseInt(offsetInput.value) + steps * offsetModel.step;
 
-    // Limit to -30 to 30
-    nextValue = Math.min(30, Math.max(-30, nextValue));
+    // Limit to the range of the offset model, the backend validates it as well
+    nextValue = Math.min(offsetModel.max, Math.max(offsetModel.min, nextValue));
 
     saveOffset(coreId, nextValue, offsetInput);
 }
 
 function saveOffset(coreId, newValue, offsetInput) {
-    invoke("set_offset", {coreId: coreId, offset: newValue});
+    const previousValue = appConfig.offset_per_core[coreId] ?? 0;
     offsetInput.value = newValue;
-    appConfig.offset_per_core[coreId] = newValue;
+    setConfigOffset(coreId, newValue);
+
+    invoke("set_offset", {coreId: coreId, offset: newValue})
+        .catch(async (error) => {
+            offsetInput.value = previousValue;
+            setConfigOffset(coreId, previousValue);
+            await showError(error);
+        });
+}
+
+// Sets the offset in the app config, also in the active profile
+function setConfigOffset(coreId, offset) {
+    appConfig.offset_per_core[coreId] = offset;
+
+    const activeProfile = appConfig.profiles.find((profile) => profile.name === appConfig.active_profile);
+    if (activeProfile) {
+        activeProfile.offset_per_core[coreId] = offset;
+    }
 }
 
 function createCpuStatusLayout(cpuTestStatus, cpuLayout) {
@@ -145,9 +547,12 @@ function createCpuStatusLayout(cpuTestStatus, cpuLayout) {
     coreId.className = "coreId";
     cpuLayout.appendChild(coreId);
 
-    // "Offset" static text
+    // "Offset" static text with the unit of the offset model
     cpuLayout.appendChild(document.createElement("br"));
-    cpuLayout.appendChild(document.createTextNode("Offset"));
+    const offsetLabel = document.createElement("span");
+    offsetLabel.className = "offsetLabel";
+    offsetLabel.innerText = `Offset (${offsetModel.unit})`;
+    cpuLayout.appendChild(offsetLabel);
     cpuLayout.appendChild(document.createElement("br"));
 
     // Create a container div for the buttons
@@ -155,7 +560,7 @@ function createCpuStatusLayout(cpuTestStatus, cpuLayout) {
     buttonContainer.className = "buttonContainer";
     cpuLayout.appendChild(buttonContainer);
 
-    // "-" Button, that reduces the offset by 1
+    // "-" Button, that reduces the offset by one step
     const offsetMinusButton = document.createElement("button");
     offsetMinusButton.innerText = "-";
     offsetMinusButton.onclick = () => addValueToOffset(cpuTestStatus.core_id, -1);
@@ -164,9 +569,11 @@ function createCpuStatusLayout(cpuTestStatus, cpuLayout) {
     const offsetInput = document.createElement("input");
     offsetInput.type = "number";
     offsetInput.id = `offset${cpuTestStatus.core_id}`;
+    offsetInput.className = "offsetInput";
     offsetInput.value = 0;
-    offsetInput.min = -30;
-    offsetInput.max = 30;
+    offsetInput.min = offsetModel.min;
+    offsetInput.max = offsetModel.max;
+    offsetInput.step = offsetModel.step;
     buttonContainer.appendChild(offsetInput);
     // Add on focus lost listener, also set the offset value to the app config
     offsetInput.addEventListener("focusout", () => {
@@ -180,7 +587,7 @@ function createCpuStatusLayout(cpuTestStatus, cpuLayout) {
         offsetInput.value = offset;
     }
 
-    // "+" Button, that increases the offset by 1
+    // "+" Button, that increases the offset by one step
     const offsetPlusButton = document.createElement("button");
     offsetPlusButton.innerText = "+";
     offsetPlusButton.onclick = () => addValueToOffset(cpuTestStatus.core_id, 1);
@@ -194,28 +601,137 @@ function createCpuStatusLayout(cpuTestStatus, cpuLayout) {
     maxClockTextNode.title = "Maximum Clock of the Core";
     cpuLayout.appendChild(maxClockTextNode);
 
-    // The test methods in one line as dedicated spans
-    const methods = cpuTestStatus.method_response;
-    // Create div that contains the method status
+    appendMethodStatusLayout(cpuTestStatus.core_id, cpuTestStatus.method_response, cpuLayout);
+    appendTestControlsLayout(cpuTestStatus.core_id, "Skip core", cpuLayout);
+
+    // The stability ledger of the core
+    const ledgerLayout = document.createElement("div");
+    ledgerLayout.id = `${cpuTestStatus.core_id}Ledger`;
+    ledgerLayout.className = "ledgerLayout";
+    cpuLayout.appendChild(ledgerLayout);
+    updateLedgerLayout(cpuTestStatus.core_id);
+
+    appendProgressLayout(cpuTestStatus.core_id, cpuLayout);
+}
+
+// The card of cores tested together, with the methods and progress like the card of a core
+function createAllCoreStatusLayout(id, allCoreStatus) {
+    const cpusLayout = document.getElementById("cpusLayout");
+    const allCoreLayout = document.createElement("div");
+    allCoreLayout.id = `cpu${id}`;
+    allCoreLayout.className = "cpuLayout";
+    cpusLayout.appendChild(allCoreLayout);
+
+    const title = document.createElement("span");
+    title.innerText = allCoreStatus.name;
+    title.className = "coreId";
+    allCoreLayout.appendChild(title);
+
+    // The cores tested together and the ones that failed
+    allCoreLayout.appendChild(document.createElement("br"));
+    const coresTextNode = document.createElement("span");
+    coresTextNode.innerText = `Cores ${allCoreStatus.core_ids.join(", ")}`;
+    allCoreLayout.appendChild(coresTextNode);
+    allCoreLayout.appendChild(document.createElement("br"));
+    const failedCoresTextNode = document.createElement("span");
+    failedCoresTextNode.id = `${id}FailedCores`;
+    allCoreLayout.appendChild(failedCoresTextNode);
+
+    appendMethodStatusLayout(id, allCoreStatus.method_response, allCoreLayout);
+    appendTestControlsLayout(id, "Skip stage", allCoreLayout);
+    appendProgressLayout(id, allCoreLayout);
+    return allCoreLayout;
+}
+
+// The test methods in one line as dedicated spans
+function appendMethodStatusLayout(id, methods, cpuLayout) {
     const methodStatusLayout = document.createElement("div");
     methodStatusLayout.className = "methodStatusLayout";
     for (const method in methods) {
         const methodStatusTextNode = document.createElement("span");
-        methodStatusTextNode.id = `${cpuTestStatus.core_id}${method}`;
+        methodStatusTextNode.id = `${id}${method}`;
         methodStatusTextNode.className = "methodStatus";
         methodStatusLayout.appendChild(methodStatusTextNode);
     }
     cpuLayout.appendChild(methodStatusLayout);
+}
+
+// Pause and skip buttons, only shown while the card is tested
+function appendTestControlsLayout(id, skipAllLabel, cpuLayout) {
+    const testControlsLayout = document.createElement("div");
+    testControlsLayout.id = `${id}TestControls`;
+    testControlsLayout.className = "testControlsLayout";
+    testControlsLayout.style.display = "none";
+    cpuLayout.appendChild(testControlsLayout);
+
+    const pauseButton = document.createElement("button");
+    pauseButton.className = "pauseButton";
+    pauseButton.onclick = () => onPauseButtonClick();
+    testControlsLayout.appendChild(pauseButton);
+
+    const skipMethodButton = document.createElement("button");
+    skipMethodButton.className = "skipButton";
+    skipMethodButton.innerText = "Skip method";
+    skipMethodButton.onclick = () => onSkipButtonClick("Method");
+    testControlsLayout.appendChild(skipMethodButton);
+
+    // Skips the remaining methods of the core or the all-core stage
+    const skipCoreButton = document.createElement("button");
+    skipCoreButton.className = "skipButton";
+    skipCoreButton.innerText = skipAllLabel;
+    skipCoreButton.onclick = () => onSkipButtonClick("Core");
+    testControlsLayout.appendChild(skipCoreButton);
+    updateTestControls();
+}
 
+function appendProgressLayout(id, cpuLayout) {
     // The Progress bar showing the time left for the current test method
     // Hidden at the beginning
     const progressBar = document.createElement("progress");
-    progressBar.id = `${cpuTestStatus.core_id}ProgressBar`;
+    progressBar.id = `${id}ProgressBar`;
     progressBar.className = "progressBar";
     progressBar.max = 100;
     progressBar.value = 0;
     progressBar.style.display = "none";
     cpuLayout.appendChild(progressBar);
+
+    // The Prime95 FFT progress shown under the progress bar
+    const prime95ProgressTextNode = document.createElement("span");
+    prime95ProgressTextNode.id = `${id}Prime95Progress`;
+    prime95ProgressTextNode.className = "prime95Progress";
+    cpuLayout.appendChild(prime95ProgressTextNode);
+
+    // The wake latency of the idle wake test
+    const wakeLatencyTextNode = document.createElement("span");
+    wakeLatencyTextNode.id = `${id}WakeLatency`;
+    wakeLatencyTextNode.className = "wakeLatency";
+    cpuLayout.appendChild(wakeLatencyTextNode);
+
+    // The temperature while waiting for the CPU to cool down after a method
+    const cooldownTextNode = document.createElement("span");
+    cooldownTextNode.id = `${id}Cooldown`;
+    cooldownTextNode.className = "cooldownStatus";
+    cpuLayout.appendChild(cooldownTextNode);
+}
+
+function updateAllCoreStatus(allCoreStatus) {
+    const id = coreSetIds[allCoreStatus.name] ??= `CoreSet${Object.keys(coreSetIds).length}`;
+    currentCoreSetId = id;
+    const allCoreLayout = document.getElementById(`cpu${id}`)
+        ?? createAllCoreStatusLayout(id, allCoreStatus);
+
+    const methods = Object.values(allCoreStatus.method_response);
+    if (methods.some((method) => method.state === "Testing")) {
+        clearCooldownStatus();
+    }
+
+    // The stage has no clock, the rest of the card is updated like the card of a core
+    updateCpuStatusLayout({core_id: id, method_response: allCoreStatus.method_response}, allCoreLayout);
+
+    const failedCoresTextNode = document.getElementById(`${id}FailedCores`);
+    failedCoresTextNode.innerText = allCoreStatus.failed_core_ids.length > 0
+        ? `Failed cores: ${allCoreStatus.failed_core_ids.join(", ")}`
+        : "";
 }
 
 function updateCpuStatusLayout(cpuTestStatus, cpuLayout) {
@@ -225,26 +741,39 @@ function updateCpuStatusLayout(cpuTestStatus, cpuLayout) {
     let isAllMethodsIdle = Object.values(methods).every((method) => method.state === "Idle");
     let isAnyMethodTesting = Object.values(methods).some((method) => method.state === "Testing");
     let isAllMethodsSuccess = Object.values(methods).every((method) => method.state === "Success");
-    let isAnyMethodFailed = Object.values(methods).some((method) => method.state === "Failed");
+    let isAnyMethodFailed = Object.values(methods).some((method) => method.state === "Failed" || method.state === "Error");
     let isAnyIdleAndAnySuccess = Object.values(methods).some((method) => method.state === "Idle") && Object.values(methods).some((method) => method.state === "Success");
 
     // Update clock speed
     let maxClockTextNode = document.getElementById(`${cpuTestStatus.core_id}Clock`);
-    setValueAnimated(maxClockTextNode, cpuTestStatus.max_clock, " MHz");
+    if (maxClockTextNode) {
+        setValueAnimated(maxClockTextNode, cpuTestStatus.max_clock, " MHz");
+    }
 
     // Update progress bar
     const progressBar = document.getElementById(`${cpuTestStatus.core_id}ProgressBar`);
     progressBar.style.display = isAnyMethodTesting ? "block" : "none";
+    const testControlsLayout = document.getElementById(`${cpuTestStatus.core_id}TestControls`);
+    testControlsLayout.style.display = isAnyMethodTesting && isTestRunning ? "flex" : "none";
     if (isAnyMethodTesting) {
         const currentMethodInTesting = Object.values(methods).find((method) => method.state === "Testing");
         progressBar.max = currentMethodInTesting.total_secs;
         progressBar.value = currentMethodInTesting.current_secs;
     }
 
+    // Update Prime95 FFT progress
+    const prime95ProgressTextNode = document.getElementById(`${cpuTestStatus.core_id}Prime95Progress`);
+    prime95ProgressTextNode.innerText = formatPrime95Progress(methods["Prime95"]);
+
+    // Update idle wake latency
+    const wakeLatencyTextNode = document.getElementById(`${cpuTestStatus.core_id}WakeLatency`);
+    wakeLatencyTextNode.innerText = formatWakeLatency(methods["IdleWake"]);
+
     // Update Test method status
     for (const method in methods) {
         const methodStatusTextNode = document.getElementById(`${cpuTestStatus.core_id}${method}`);
         methodStatusTextNode.innerText = `${method}`;
+        methodStatusTextNode.title = methods[method].error ?? "";
         switch (methods[method].state) {
             case "Idle":
                 methodStatusTextNode.style.borderColor = "transparent";
@@ -258,6 +787,15 @@ function updateCpuStatusLayout(cpuTestStatus, cpuLayout) {
             case "Failed":
                 methodStatusTextNode.style.borderColor = "#ff0000";
                 break;
+            case "Error":
+                methodStatusTextNode.style.borderColor = "#ff8c00";
+                break;
+            case "Skipped":
+                methodStatusTextNode.style.borderColor = "#808080";
+                break;
+            case "ThermalAbort":
+                methodStatusTextNode.style.borderColor = "#ff00ff";
+                break;
         }
     }
 
@@ -282,6 +820,34 @@ function updateCpuStatusLayout(cpuTestStatus, cpuLayout) {
     cpuLayout.className = className;
 }
 
+// Formats the Prime95 progress, e.g. "FFT 1120K, 3 passes"
+function formatPrime95Progress(prime95Response) {
+    const progress = prime95Response?.prime95_progress;
+    if (!progress) {
+        return "";
+    }
+
+    if (progress.failed_fft_length) {
+        return `Failed at FFT ${progress.failed_fft_length}`;
+    }
+
+    if (prime95Response.state !== "Testing" || !progress.current_fft_length) {
+        return "";
+    }
+
+    return `FFT ${progress.current_fft_length}, ${progress.passes} passes`;
+}
+
+// Formats the wake latency, e.g. "1200 wakes, 85 µs avg, 410 µs max"
+function formatWakeLatency(idleWakeResponse) {
+    const latency = idleWakeResponse?.wake_latency;
+    if (!latency || latency.wakes === 0) {
+        return "";
+    }
+
+    return `${latency.wakes} wakes, ${latency.average_micros} µs avg, ${latency.max_micros} µs max`;
+}
+
 function setValueAnimated(textInput, nextValue, suffix) {
     let currentValue = parseInt(textInput.innerText);
 
@@ -304,40 +870,89 @@ function setValueAnimated(textInput, nextValue, suffix) {
     }, 50);
 }
 
-function startStatusPolling() {
-    timer = setInterval(() => updateTestStatus(), 1000);
+// Shows all cores of the started test, later changes are pushed as events
+function loadTestStatus() {
+    invoke("get_test_status").then((testStatus) => {
+        testStatus
+            .filter((cpuTestStatus) => !testStatusPerCore[cpuTestStatus.core_id])
+            .forEach((cpuTestStatus) => updateCpuStatus(cpuTestStatus));
+    }).then(() => invoke("get_all_core_status")).then((allCoreStatus) => {
+        // Shown after the cores, as it runs last
+        if (allCoreStatus) {
+            updateAllCoreStatus(allCoreStatus);
+        }
+    });
 }
 
-function isWholeTestDone(testStatus) {
-    // Collect all test method results
-    let allMethods = testStatus.flatMap(cpuTestStatus => Object.values(cpuTestStatus.method_response));
+function onMethodProgress(progress) {
+    const cpuTestStatus = testStatusPerCore[progress.core_id];
+    if (!cpuTestStatus) {
+        return;
+    }
 
-    // Check if all states are either success or failed
-    return allMethods.every(method => method.state === "Success" || method.state === "Failed");
+    const methodResponse = progress.method_response;
+    cpuTestStatus.method_response[methodResponse.method] = methodResponse;
+    if (methodResponse.state === "Testing") {
+        clearCooldownStatus();
+    }
+    updateCpuStatus(cpuTestStatus);
 }
 
-function updateTestStatus() {
-    invoke("get_test_status").then((results) => {
-        const testStatus = JSON.parse(results);
+function onClockUpdate(clockUpdate) {
+    const cpuTestStatus = testStatusPerCore[clockUpdate.core_id];
+    if (!cpuTestStatus) {
+        return;
+    }
+
+    cpuTestStatus.max_clock = clockUpdate.max_clock;
+    updateCpuStatus(cpuTestStatus);
+}
 
-        testStatus.forEach((cpuTestStatus) => {
-            updateCpuStatus(cpuTestStatus);
-        });
+function onTestFailure(failure) {
+    const cpuTestStatus = testStatusPerCore[failure.core_id];
+    if (!cpuTestStatus) {
+        return;
+    }
 
-        if (isWholeTestDone(testStatus)) {
-            stopTest();
-            showSummary(testStatus);
-        }
+    cpuTestStatus.verification_failed = true;
+    updateCpuStatus(cpuTestStatus);
+}
+
+// Shows the temperature on the card of the core or the cores tested together that are cooling down
+function onCooldown(cooldown) {
+    clearCooldownStatus();
+    const cooldownTextNode = document.getElementById(`${cooldown.core_id ?? currentCoreSetId}Cooldown`);
+    if (cooldownTextNode) {
+        cooldownTextNode.innerText = `Cooling down: ${cooldown.temperature.toFixed(1)} °C → ${cooldown.target} °C`;
+    }
+}
+
+function clearCooldownStatus() {
+    document.querySelectorAll(".cooldownStatus").forEach((cooldownTextNode) => {
+        cooldownTextNode.innerText = "";
     });
-    // .catch((error) => {
-    // console.error("Error while getting test status: " + error);
-    // });
+}
+
+function onRunFinished(runFinished) {
+    clearCooldownStatus();
+    runFinished.test_status.forEach((cpuTestStatus) => updateCpuStatus(cpuTestStatus));
+    if (runFinished.all_core_status) {
+        updateAllCoreStatus(runFinished.all_core_status);
+    }
+    stopTest();
+
+    // A run stopped by the user has no meaningful result
+    if (!runFinished.stopped_by_user) {
+        showSummary(runFinished.test_status, runFinished.all_core_status, runFinished.load_sweep_status);
+        showOffsetSuggestions(false);
+        loadLedger();
+    }
 }
 
 // Shows a summary of the test results
 // If all cores passed the test, it will show a success message
 // If any core failed the test, it will show a list of the failed cores
-function showSummary(testStatus) {
+function showSummary(testStatus, allCoreStatus, loadSweepStatus) {
     const summaryLayout = document.getElementById("summaryLayout");
     summaryLayout.innerHTML = "";
 
@@ -346,26 +961,236 @@ function showSummary(testStatus) {
         return Object.values(methods).some((method) => method.state === "Failed");
     });
 
+    const erroredMethods = testStatus.flatMap((cpuTestStatus) => {
+        return Object.values(cpuTestStatus.method_response)
+            .filter((method) => method.state === "Error")
+            .map((method) => `Core ${cpuTestStatus.core_id} ${method.method}: ${method.error}`);
+    });
+
+    const thermalAborts = testStatus.flatMap((cpuTestStatus) => {
+        return Object.values(cpuTestStatus.method_response)
+            .filter((method) => method.state === "ThermalAbort")
+            .map((method) => `Core ${cpuTestStatus.core_id} ${method.method}: ${method.error}`);
+    });
+
+    const skippedMethods = testStatus.flatMap((cpuTestStatus) => {
+        return Object.values(cpuTestStatus.method_response)
+            .filter((method) => method.state === "Skipped")
+            .map((method) => `Core ${cpuTestStatus.core_id} ${method.method}`);
+    });
+
     if (failedCores.length > 0) {
         const div = document.createElement("div");
         div.innerText = "Failed cores: " + failedCores
-            .map((cpuTestStatus) => cpuTestStatus.core_id)
+            .map((cpuTestStatus) => {
+                const failedFft = cpuTestStatus.method_response["Prime95"]?.prime95_progress?.failed_fft_length;
+                return failedFft ? `${cpuTestStatus.core_id} (FFT ${failedFft})` : cpuTestStatus.core_id;
+            })
             .join(", ");
         summaryLayout.appendChild(div);
-    } else {
+    }
+
+    if (erroredMethods.length > 0) {
+        const div = document.createElement("div");
+        div.innerText = "Test program errors: " + erroredMethods.join(", ");
+        summaryLayout.appendChild(div);
+    }
+
+    if (thermalAborts.length > 0) {
+        const div = document.createElement("div");
+        div.innerText = "Thermal aborts: " + thermalAborts.join(", ");
+        summaryLayout.appendChild(div);
+    }
+
+    if (skippedMethods.length > 0) {
+        const div = document.createElement("div");
+        div.innerText = "Skipped: " + skippedMethods.join(", ");
+        summaryLayout.appendChild(div);
+    }
+
+    // The load sweep does not test single cores
+    if (loadSweepStatus) {
+        formatLoadSweepSummary(loadSweepStatus).forEach((line) => {
+            const div = document.createElement("div");
+            div.innerText = line;
+            summaryLayout.appendChild(div);
+        });
+    } else if (failedCores.length === 0 && erroredMethods.length === 0) {
         const div = document.createElement("div");
-        div.innerText = "All cores passed the test";
+        div.innerText = skippedMethods.length > 0 || thermalAborts.length > 0
+            ? "All tested cores passed the test"
+            : "All cores passed the test";
         summaryLayout.appendChild(div);
     }
+
+    if (allCoreStatus) {
+        const div = document.createElement("div");
+        div.innerText = formatAllCoreSummary(allCoreStatus);
+        summaryLayout.appendChild(div);
+    }
+}
+
+// Formats the result of the all-core stage, e.g. "All-core stage failed on cores 3, 5"
+function formatAllCoreSummary(allCoreStatus) {
+    if (allCoreStatus.failed_core_ids.length > 0) {
+        return `All-core stage failed on cores ${allCoreStatus.failed_core_ids.join(", ")}`;
+    }
+
+    const incompleteMethods = Object.values(allCoreStatus.method_response)
+        .filter((method) => method.state !== "Success")
+        .map((method) => method.error ? `${method.method} (${method.error})` : `${method.method} (${method.state})`);
+    if (incompleteMethods.length > 0) {
+        return "All-core stage incomplete: " + incompleteMethods.join(", ");
+    }
+
+    return "All-core stage passed";
+}
+
+// Formats the findings of the load sweep, e.g. "L3 group 0 failed with cores 0, 1, 2, 3 loaded, bisected to core 2"
+function formatLoadSweepSummary(loadSweepStatus) {
+    if (loadSweepStatus.findings.length === 0) {
+        const incomplete = loadSweepStatus.steps.some((step) => {
+            return Object.values(step.method_response).some((method) => method.state !== "Success");
+        });
+        return [incomplete ? "Load sweep incomplete, no set of cores failed" : "Load sweep passed"];
+    }
+
+    return loadSweepStatus.findings.map((finding) => {
+        if (finding.failed_set.length === 1) {
+            return `L3 group ${finding.l3_group} failed with only core ${finding.failed_set[0]} loaded`;
+        }
+        const failedSet = `L3 group ${finding.l3_group} failed with cores ${finding.failed_set.join(", ")} loaded`;
+        if (finding.offending_core_ids.length === 1) {
+            return `${failedSet}, bisected to core ${finding.offending_core_ids[0]}`;
+        }
+        return `${failedSet}, fails only with cores ${finding.offending_core_ids.join(", ")} together`;
+    });
+}
+
+// Shows the suggested offsets of the last run in the summary, with a button to apply them at once
+function showOffsetSuggestions(includePassed) {
+    invoke("get_offset_suggestions", {includePassed: includePassed}).then((suggestions) => {
+        const summaryLayout = document.getElementById("summaryLayout");
+
+        let suggestionsLayout = document.getElementById("suggestionsLayout");
+        if (!suggestionsLayout) {
+            suggestionsLayout = document.createElement("div");
+            suggestionsLayout.id = "suggestionsLayout";
+            summaryLayout.appendChild(suggestionsLayout);
+        }
+        suggestionsLayout.innerHTML = "";
+
+        const suggestionsText = document.createElement("span");
+        suggestionsText.innerText = suggestions.length > 0
+            ? "Suggested offsets: " + suggestions
+            .map((suggestion) => `${suggestion.core_id}: ${suggestion.current} → ${suggestion.suggested}`)
+            .join(", ")
+            : "No offset suggestions";
+        suggestionsLayout.appendChild(suggestionsText);
+
+        // Passed cores can optionally try a more negative offset
+        const includePassedCheckbox = document.createElement("input");
+        includePassedCheckbox.type = "checkbox";
+        includePassedCheckbox.id = "includePassedCheckbox";
+        includePassedCheckbox.checked = includePassed;
+        includePassedCheckbox.addEventListener("change", () => showOffsetSuggestions(includePassedCheckbox.checked));
+        suggestionsLayout.appendChild(includePassedCheckbox);
+
+        const includePassedLabel = document.createElement("label");
+        includePassedLabel.htmlFor = "includePassedCheckbox";
+        includePassedLabel.innerText = "Include passed cores";
+        suggestionsLayout.appendChild(includePassedLabel);
+
+        const applyButton = document.createElement("button");
+        applyButton.innerText = "Apply suggestions";
+        applyButton.disabled = suggestions.length === 0;
+        applyButton.onclick = () => applyOffsetSuggestions(includePassed);
+        suggestionsLayout.appendChild(applyButton);
+    });
+}
+
+function applyOffsetSuggestions(includePassed) {
+    invoke("apply_offset_suggestions", {includePassed: includePassed}).then((config) => {
+        onProfilesChanged(config);
+        document.getElementById("suggestionsLayout").innerText = "Suggested offsets applied";
+    }).catch(showError);
+}
+
+// Loads the stability ledger and shows it on the core cards
+function loadLedger() {
+    invoke("get_ledger").then((ledger) => {
+        ledgerPerCore = {};
+        ledger.forEach((coreLedger) => {
+            ledgerPerCore[coreLedger.core_id] = coreLedger;
+        });
+
+        Object.keys(ledgerPerCore).forEach((coreId) => updateLedgerLayout(coreId));
+    }).catch(showError);
+}
+
+// Shows the tested offsets of the core as table, e.g. "-20 | 3/0 | 30m", and the recommended offset
+function updateLedgerLayout(coreId) {
+    const ledgerLayout = document.getElementById(`${coreId}Ledger`);
+    const coreLedger = ledgerPerCore[coreId];
+    if (!ledgerLayout || !coreLedger) {
+        return;
+    }
+
+    ledgerLayout.innerHTML = "";
+
+    const recommendation = document.createElement("span");
+    recommendation.innerText = coreLedger.recommended_offset !== null
+        ? `Safe offset: ${coreLedger.recommended_offset}`
+        : "No safe offset yet";
+    ledgerLayout.appendChild(recommendation);
+
+    const table = document.createElement("table");
+    const header = table.insertRow();
+    ["Offset", "Pass", "Fail", "Time"].forEach((title) => {
+        const cell = document.createElement("th");
+        cell.innerText = title;
+        header.appendChild(cell);
+    });
+
+    Object.entries(coreLedger.records)
+        .sort(([offsetA], [offsetB]) => offsetA - offsetB)
+        .forEach(([offset, record]) => {
+            const row = table.insertRow();
+            row.title = `${record.methods.join(", ")}\nLast tested: ${record.last_tested}`;
+            row.insertCell().innerText = offset;
+            row.insertCell().innerText = record.passed;
+            row.insertCell().innerText = record.failed;
+            row.insertCell().innerText = `${Math.round(record.total_secs / 60)}m`;
+        });
+    ledgerLayout.appendChild(table);
 }
 
 function loadTestMethods() {
     invoke("get_test_methods").then((methods) => {
-        JSON.parse(methods)
+        methods
             .forEach((method) => {
                 const div = createTestMethodCheckbox(method);
                 testMethodsLayout.appendChild(div);
             });
+    }).then(() => loadBinaryInfo());
+}
+
+// Shows which binary and version each test method uses
+function loadBinaryInfo() {
+    invoke("get_binary_info").then((binaryInfo) => {
+        binaryInfo
+            .forEach((info) => {
+                const label = document.querySelector(`label[for="${info.method}Checkbox"]`);
+                if (!label) {
+                    return;
+                }
+
+                const version = info.version ?? "unknown version";
+                label.title = `${info.source}: ${info.path} (${version})`;
+                if (info.warning) {
+                    label.title += `\n${info.warning}`;
+                }
+            });
     });
 }
 
@@ -394,3 +1219,37 @@ function createTestMethodCheckbox(testMethodName) {
     div.appendChild(label);
     return div;
 }
+
+function loadYCruncherTests() {
+    invoke("get_ycruncher_tests").then((tests) => {
+        tests
+            .forEach((test) => {
+                const div = createYCruncherTestCheckbox(test);
+                ycruncherTestsLayout.appendChild(div);
+            });
+    });
+}
+
+function createYCruncherTestCheckbox(testName) {
+    const div = document.createElement("div");
+    div.id = testName + "YCruncherLayout";
+    div.className = "ycruncherTest";
+
+    // Add checkbox
+    const checkbox = document.createElement("input");
+    checkbox.type = "checkbox";
+    checkbox.id = testName + "YCruncherCheckbox";
+    checkbox.value = testName;
+    div.appendChild(checkbox);
+
+    // Create Label for Checkbox
+    const label = document.createElement("label");
+    label.htmlFor = testName + "YCruncherCheckbox";
+    label.appendChild(document.createTextNode(testName));
+
+    // Check if the test is present in the app config "ycruncher.tests"
+    checkbox.checked = appConfig.ycruncher.tests.includes(testName);
+
+    div.appendChild(label);
+    return div;
+}